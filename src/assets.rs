@@ -10,6 +10,8 @@ pub struct GameAssets {
   gameover_sound: Handle<AudioSource>,
   /// sound effect for when a line is cleared.
   line_clear_sound: Handle<AudioSource>,
+  /// sound effect for each second of a countdown.
+  warning_sound: Handle<AudioSource>,
   /// bold font for the game.
   main_bold_font: Handle<Font>,
   /// icon for the game.
@@ -25,6 +27,10 @@ impl GameAssets {
   pub fn play_line_clear(&self, audio: &Res<Audio>) {
     audio.play(self.line_clear_sound.clone());
   }
+  /// play the countdown warning sound effect.
+  pub fn play_warning(&self, audio: &Res<Audio>) {
+    audio.play(self.warning_sound.clone());
+  }
   /// play the game over sound effect.
   pub fn play_gameover(&self, audio: &Res<Audio>) {
     audio.play(self.gameover_sound.clone());
@@ -64,6 +70,7 @@ impl FromWorld for GameAssets {
       drop_sound: assets.load("sounds/Drop.wav"),
       gameover_sound: assets.load("sounds/Gameover.wav"),
       line_clear_sound: assets.load("sounds/Lineclear.wav"),
+      warning_sound: assets.load("sounds/Warning.wav"),
       main_bold_font: assets.load("fonts/FiraSans-Bold.ttf"),
       game_icon: assets.load("icon.png"),
     }
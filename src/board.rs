@@ -1,4 +1,6 @@
 //! the game board and its logic.
+use std::collections::HashMap;
+
 use crate::prelude::*;
 
 /// the overall game board.
@@ -7,20 +9,26 @@ pub struct GameBoard;
 impl Plugin for GameBoard {
   fn build(&self, app: &mut App) {
     app
-      .insert_resource(GameBoardData::default())
-      .add_startup_system(Self::setup)
+      .init_resource::<Settings>()
+      .init_resource::<BoardConfig>()
+      .init_resource::<GameEngine>()
+      .add_event::<EngineEvent>()
+      .add_system(Self::setup)
       .add_system(Tile::clear_all.in_schedule(OnEnter(GameState::Restarting)));
   }
 }
 
-#[derive(Resource, Debug, Default, Copy, Clone)]
+/// [resource][`Resource`] holding the [`Engine`] of the game being played, so
+/// the engine itself stays free of the ECS.
+#[derive(Debug, Default, Resource, Deref, DerefMut)]
+pub struct GameEngine(pub Engine);
+
+#[derive(Debug, Default, Copy, Clone)]
 pub struct GameBoardData {
-  /// has the player held a piece (since the last piece was spawned)?
+  /// has the player held a piece (since the last piece locked)?
   pub held: bool,
   /// the piece that the player is holding.
   pub held_piece: Option<Shape>,
-  /// next piece to be spawned.
-  pub next_piece: Option<Shape>,
 }
 
 impl GameBoardData {
@@ -30,10 +38,14 @@ impl GameBoardData {
   }
 }
 
+/// marks the walls around the game board.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct BoardFrame;
+
 impl GameBoard {
-  /// how many block units wide is the board?
+  /// how many block units wide is the standard board?
   pub const COL_COUNT: u8 = 10;
-  /// how many block units tall is the board?
+  /// how many block units tall is the standard board?
   pub const ROW_COUNT: u8 = 20;
   /// how many total spaces is the board?
   #[allow(unused)]
@@ -43,215 +55,540 @@ impl GameBoard {
   // what color is the border of the board?
   pub const BORDER_COLOR: Color = palette::SURFACE1;
 
-  /// setup the game board [system][`System`].
-  fn setup(mut commands: Commands) {
-    let col_midpoint = f32::from(GameBoard::COL_COUNT) / 2.0;
-    let row_midpoint = f32::from(GameBoard::ROW_COUNT) / 2.0;
+  /// setup the game board [system][`System`]. (re)builds the walls and
+  /// frames the camera around them whenever the [`BoardConfig`] changes.
+  fn setup(
+    mut commands: Commands,
+    config: Res<BoardConfig>,
+    walls: Query<Entity, With<BoardFrame>>,
+    mut cameras: Query<&mut OrthographicProjection>,
+  ) {
+    if !config.is_changed() {
+      return;
+    }
+    for entity in &walls {
+      commands.entity(entity).despawn();
+    }
+    // big mode halves the cells but doubles their size, so the frame fits either.
+    let size = Geometry::new(&config, 1).size();
+    for wall in Self::spawn_frame(&mut commands, Vec2::ZERO, size, "GameBoard") {
+      commands.entity(wall).insert(BoardFrame);
+    }
+    // zoom out just enough for boards taller than the standard one to fit.
+    let scale = (size.y / Geometry::default().size().y).max(1.0);
+    for mut projection in &mut cameras {
+      projection.scale = scale;
+    }
+  }
+
+  /// spawns walls around an area of the given `size` (in pixels), centered
+  /// at `center`, and returns them as left, right, top and bottom.
+  pub fn spawn_frame(commands: &mut Commands, center: Vec2, size: Vec2, name: &str) -> [Entity; 4] {
     let z = 0.0;
     let wall_sprite = Sprite {
       color: GameBoard::BORDER_COLOR,
       ..default()
     };
-    commands
-      .spawn(SpriteBundle {
-        transform: Transform {
-          translation: Vec3 {
-            x: (-col_midpoint).mul_add(Tile::LENGTH, -GameBoard::BORDER_THICKNESS / 2.0),
+    let mut wall = |translation: Vec2, scale: Vec2, side: &str| {
+      commands
+        .spawn(SpriteBundle {
+          transform: Transform {
+            translation: (center + translation).extend(z),
+            scale: scale.extend(z),
             ..default()
           },
-          scale: Vec3 {
-            x: GameBoard::BORDER_THICKNESS,
-            y: f32::from(GameBoard::ROW_COUNT)
-              .mul_add(Tile::LENGTH, 2.0 * GameBoard::BORDER_THICKNESS),
-            z,
-          },
-          ..default()
-        },
-        sprite: wall_sprite.clone(),
-        ..default()
-      })
-      .insert(Name::new("GameBoard Wall (Left)"));
-    commands
-      .spawn(SpriteBundle {
-        transform: Transform {
-          translation: Vec3 {
-            x: col_midpoint.mul_add(Tile::LENGTH, GameBoard::BORDER_THICKNESS / 2.0),
-            ..default()
-          },
-          scale: Vec3 {
-            x: GameBoard::BORDER_THICKNESS,
-            y: f32::from(GameBoard::ROW_COUNT)
-              .mul_add(Tile::LENGTH, 2.0 * GameBoard::BORDER_THICKNESS),
-            z,
-          },
-          ..default()
-        },
-        sprite: wall_sprite.clone(),
-        ..default()
-      })
-      .insert(Name::new("GameBoard Wall (Right)"));
-    commands
-      .spawn(SpriteBundle {
-        transform: Transform {
-          translation: Vec3 {
-            y: row_midpoint.mul_add(Tile::LENGTH, GameBoard::BORDER_THICKNESS / 2.0),
-            ..default()
-          },
-          scale: Vec3 {
-            x: f32::from(GameBoard::COL_COUNT) * Tile::LENGTH,
-            y: GameBoard::BORDER_THICKNESS,
-            z,
-          },
+          sprite: wall_sprite.clone(),
           ..default()
-        },
-        sprite: wall_sprite.clone(),
-        ..default()
-      })
-      .insert(Name::new("GameBoard Wall (Top)"));
-    commands
-      .spawn(SpriteBundle {
-        transform: Transform {
-          translation: Vec3 {
-            y: (-row_midpoint).mul_add(Tile::LENGTH, -GameBoard::BORDER_THICKNESS / 2.0),
-            ..default()
-          },
-          scale: Vec3 {
-            x: f32::from(GameBoard::COL_COUNT) * Tile::LENGTH,
-            y: GameBoard::BORDER_THICKNESS,
-            z,
-          },
-          ..default()
-        },
-        sprite: wall_sprite,
-        ..default()
-      })
-      .insert(Name::new("GameBoard Wall (Bottom)"));
+        })
+        .insert(Name::new(format!("{name} Wall ({side})")))
+        .id()
+    };
+    let offset = (size + GameBoard::BORDER_THICKNESS) / 2.0;
+    let vertical = Vec2::new(GameBoard::BORDER_THICKNESS, 2.0f32.mul_add(GameBoard::BORDER_THICKNESS, size.y));
+    let horizontal = Vec2::new(size.x, GameBoard::BORDER_THICKNESS);
+    [
+      wall(Vec2::new(-offset.x, 0.0), vertical, "Left"),
+      wall(Vec2::new(offset.x, 0.0), vertical, "Right"),
+      wall(Vec2::new(0.0, offset.y), horizontal, "Top"),
+      wall(Vec2::new(0.0, -offset.y), horizontal, "Bottom"),
+    ]
   }
 
+  /// [system][`System`] which discards the current game in favor of a fresh one.
+  pub fn reset(mut engine: ResMut<GameEngine>, settings: Res<Settings>, config: Res<BoardConfig>) {
+    engine.reset(&settings, &config);
+  }
+
+  /// [system][`System`] which feeds the player's input to the [`Engine`] and
+  /// forwards whatever happened as [`EngineEvent`]s.
+  pub fn step(
+    mut engine: ResMut<GameEngine>,
+    mut events: EventWriter<EngineEvent>,
+    kb: Res<Input<KeyCode>>,
+    time: Res<Time>,
+  ) {
+    let inputs = crate::keymap::read_inputs(&kb);
+    let happened = engine.step(inputs, time.delta());
+    for event in &happened {
+      match event {
+        EngineEvent::ToppedOut(top_out) => info!("player topped out: {top_out}; emptying the matrix."),
+        EngineEvent::GameOver(outcome) => info!("game over: {outcome}."),
+        _ => {}
+      }
+    }
+    events.send_batch(happened);
+  }
+
+  /// [system][`System`] which mirrors the [`Engine`]'s falling piece onto its [`Tile`] sprites.
+  pub fn sync_piece(
+    mut commands: Commands,
+    engine: Res<GameEngine>,
+    mut q: Query<(Entity, &Shape, &mut Tile, &mut Transform)>,
+  ) {
+    match &engine.piece {
+      Some(piece)
+        if q.iter().count() == piece.blocks.len()
+          && q.iter().all(|(_, shape, ..)| *shape == piece.shape) =>
+      {
+        for ((_, _, mut tile, mut transform), block) in q.iter_mut().zip(piece.blocks) {
+          *tile = block;
+          transform.translation = engine.geometry.translation(block);
+        }
+      }
+      piece => {
+        for (entity, ..) in &q {
+          commands.entity(entity).despawn();
+        }
+        if let Some(piece) = piece {
+          let color = engine.rotation_system().color(piece.shape);
+          piece.clone().spawn_movable(&mut commands, &engine.geometry, color);
+        }
+      }
+    }
+  }
+
+  /// [system][`System`] which re-spawns the settled [`Tile`]s whenever a
+  /// piece locks, full rows are removed, or garbage rises. after rows are
+  /// removed, the tiles above them start where they were and [`Settling`]
+  /// down. tiles keep [`Fade`]ing from wherever they were, while tiles which
+  /// just locked start to fade as the [`StackVisibility`] has them.
+  pub fn sync_stack(
+    mut commands: Commands,
+    engine: Res<GameEngine>,
+    mut events: EventReader<EngineEvent>,
+    q: Query<(Entity, &Tile, Option<&Fade>), Without<Shape>>,
+  ) {
+    let mut changed = false;
+    let mut revealed = false;
+    // the row each row of the matrix was before these events, if it was there at all.
+    let height = engine.board.height();
+    let mut origin: Vec<Option<usize>> = (0..height).map(Some).collect();
+    for event in events.iter() {
+      match event {
+        EngineEvent::Locked(_) => changed = true,
+        EngineEvent::LinesCleared(_) => revealed = true,
+        EngineEvent::Collapsed(rows) => {
+          changed = true;
+          origin = (0..height)
+            .filter(|y| !rows.contains(y))
+            .map(|y| origin[y])
+            .collect();
+          origin.resize(height, None);
+        }
+        EngineEvent::GarbageRose(rows) => {
+          changed = true;
+          origin.splice(0..0, std::iter::repeat_n(None, *rows));
+          origin.truncate(height);
+        }
+        // the matrix was replaced, so tiles can only be matched up by where they are.
+        EngineEvent::ToppedOut(_) | EngineEvent::Undone => {
+          changed = true;
+          origin = (0..height).map(Some).collect();
+        }
+        _ => {}
+      }
+    }
+    if !changed {
+      return;
+    }
+    // how every settled tile was fading, by where it was.
+    let mut fades = HashMap::new();
+    for (entity, tile, fade) in &q {
+      fades.insert((tile.0, tile.1), fade.cloned());
+      commands.entity(entity).despawn();
+    }
+    let stack = engine.settings.stack;
+    let reveal = stack.on_clear().filter(|_| revealed);
+    for (tile, cell) in engine.board.tiles() {
+      let visibility = if engine.geometry.is_visible(tile) {
+        Visibility::Visible
+      } else {
+        Visibility::Hidden
+      };
+      let mut sprite = tile.new_sprite(&engine.geometry, cell.color(engine.rotation_system()), visibility);
+      let mut entity = commands.spawn_empty();
+      let from = origin.get(tile.1 as usize).copied().flatten();
+      let fade = match from.and_then(|from| fades.remove(&(tile.0, from as i32))) {
+        Some(fade) => reveal.clone().or(fade),
+        // it was not there before, so it just locked or rose.
+        None => reveal.clone().or_else(|| stack.on_lock()),
+      };
+      if let Some(fade) = fade {
+        sprite.sprite.color.set_a(fade.alpha());
+        entity.insert(fade);
+      }
+      if let Some(from) = from.filter(|from| *from > tile.1 as usize) {
+        let from = engine.geometry.translation(Tile(tile.0, from as i32)).y;
+        entity.insert(Settling::new(from, sprite.transform.translation.y));
+        sprite.transform.translation.y = from;
+      }
+      entity
+        .insert(sprite)
+        .insert(tile)
+        .insert(Name::new(format!("{} ({},{})", cell, tile.0, tile.1)));
+    }
+  }
+
+  /// [system][`System`] which mirrors the [`Engine`]'s phase onto [`PlayPhase`].
+  pub fn sync_phase(
+    engine: Res<GameEngine>,
+    phase: Res<State<PlayPhase>>,
+    mut next_phase: ResMut<NextState<PlayPhase>>,
+  ) {
+    if phase.0 != engine.phase {
+      next_phase.set(engine.phase);
+    }
+  }
+
+  /// [system][`System`] which dissolves the rows being cleared over the line clear delay.
+  pub fn animate_line_clear(
+    engine: Res<GameEngine>,
+    mut q: Query<(&Tile, &mut Sprite, &mut Transform), Without<Shape>>,
+  ) {
+    let progress = engine.timers.delay.percent();
+    for (tile, mut sprite, mut transform) in &mut q {
+      if !engine.clearing.contains(&(tile.1 as usize)) {
+        continue;
+      }
+      // flash white, then shrink away.
+      sprite.color = if progress < 0.25 {
+        palette::TEXT
+      } else {
+        sprite.color.with_a(1.0 - progress)
+      };
+      transform.scale = Vec3::splat(engine.geometry.sticker_length() * (1.0 - progress));
+    }
+  }
+
+  /// [system][`System`] which fades [`Fade`]ing tiles out of sight.
+  pub fn fade_tiles(time: Res<Time>, mut q: Query<(&mut Fade, &mut Sprite)>) {
+    for (mut fade, mut sprite) in &mut q {
+      fade.timer.tick(time.delta());
+      sprite.color.set_a(fade.alpha());
+    }
+  }
+
+  /// [system][`System`] which eases [`Settling`] tiles down onto the stack.
+  pub fn settle_tiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q: Query<(Entity, &mut Settling, &mut Transform)>,
+  ) {
+    for (entity, mut settling, mut transform) in &mut q {
+      settling.timer.tick(time.delta());
+      // ease out: fast at first, then slowing to a stop.
+      let t = 1.0 - (1.0 - settling.timer.percent()).powi(3);
+      transform.translation.y = (settling.to - settling.from).mul_add(t, settling.from);
+      if settling.timer.finished() {
+        commands.entity(entity).remove::<Settling>();
+      }
+    }
+  }
 
 /// a [`System`] which controls the visibility of the [`Tile`]s. if they are
 /// above the top of the board, they are hidden.
-pub fn update_tile_visibility(mut q: Query<(&mut Visibility, &Tile), With<Shape>>) {
+pub fn update_tile_visibility(engine: Res<GameEngine>, mut q: Query<(&mut Visibility, &Tile), With<Shape>>) {
   for (mut vis, block) in &mut q {
-    *vis = if block.1 > (GameBoard::ROW_COUNT as i32 - 1) {
-      Visibility::Hidden
-    } else {
+    *vis = if engine.geometry.is_visible(*block) {
       Visibility::Visible
+    } else {
+      Visibility::Hidden
     };
   }
 }
 }
 
-/// [system][`System`] responsible for detecting a "game over" state and transitioning to it.
-pub fn detect_game_over(
-  mut app_state: ResMut<NextState<AppState>>,
-  mut game_state: ResMut<NextState<GameState>>,
-  query: Query<&Tile, Without<Shape>>,
-  audio: Res<Audio>,
-  game_audio: Res<GameAssets>,
-) {
-  let mut max_block_y = 0;
-  for block in &query {
-    if block.1 > max_block_y {
-      max_block_y = block.1;
+/// the size of the visible field, in cells, and of each cell on screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geometry {
+  /// how many columns wide is the field?
+  pub cols: usize,
+  /// how many rows tall is the visible field?
+  pub rows: usize,
+  /// how many of the standard cells each cell covers, across and up. two in big mode.
+  pub scale: usize,
+}
+
+impl Default for Geometry {
+  fn default() -> Self {
+    Self::new(&BoardConfig::default(), 1)
+  }
+}
+
+impl Geometry {
+  /// creates the field for a board, with each cell covering `scale` by `scale` standard cells.
+  #[must_use]
+  pub fn new(config: &BoardConfig, scale: usize) -> Self {
+    Self {
+      cols: config.width / scale,
+      rows: config.height / scale,
+      scale,
     }
   }
-  // info!("max_block_y: {}", max_block_y);
-  if max_block_y >= (GameBoard::ROW_COUNT as i32 - 1) {
-    game_audio.play_gameover(&audio);
-    app_state.set(AppState::GameOver);
-    game_state.set(GameState::Quitting);
+
+  /// how long is each side of a cell, in pixels?
+  #[must_use]
+  pub fn cell_length(&self) -> f32 {
+    Tile::LENGTH * self.scale as f32
+  }
+
+  /// how long is each side of a tile's sticker, in pixels?
+  #[must_use]
+  pub fn sticker_length(&self) -> f32 {
+    Tile::STICKER_LENGTH * self.scale as f32
+  }
+
+  /// how large is the visible field, in pixels?
+  #[must_use]
+  pub fn size(&self) -> Vec2 {
+    Vec2::new(self.cols as f32, self.rows as f32) * self.cell_length()
+  }
+
+  /// returns where the middle of a tile is drawn.
+  #[must_use]
+  pub fn translation(&self, tile: Tile) -> Vec3 {
+    Vec3 {
+      x: (tile.0 as f32 - self.cols as f32 / 2.0 + 0.5) * self.cell_length(),
+      y: (tile.1 as f32 - self.rows as f32 / 2.0 + 0.5) * self.cell_length(),
+      z: 0.0,
+    }
+  }
+
+  /// returns true if a tile is in the visible field, rather than above it.
+  #[must_use]
+  pub fn is_visible(&self, tile: Tile) -> bool {
+    tile.1 < self.rows as i32
   }
 }
 
-/// [system][`System`] responsible for unmarking falling pieces as [`Movable`].
-pub fn remove_piece_component(
-  mut commands: Commands,
-  q_piece_blocks: Query<(Entity, &Movable), With<Shape>>,
-  mut game_timers: ResMut<GameTimers>,
-  kb: Res<Input<KeyCode>>,
-  time: Res<Time>,
-) {
-  if !q_piece_blocks.is_empty() && !q_piece_blocks.iter().last().unwrap().1.can_down {
-    if !q_piece_blocks.iter().last().unwrap().1.can_down {
-      game_timers.rm_pieces.tick(time.delta());
-    } else {
-      game_timers.rm_pieces.reset();
+/// [resource][`Resource`] holding the size of the board which new games are played on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource, Reflect)]
+pub struct BoardConfig {
+  /// how many columns wide is the board, between [`BoardConfig::MIN_WIDTH`] and [`crate::engine::Board::MAX_WIDTH`]?
+  pub width: usize,
+  /// how many rows of the board are visible?
+  pub height: usize,
+  /// how many hidden rows sit above the visible ones, for pieces to spawn into.
+  pub buffer: usize,
+}
+
+impl Default for BoardConfig {
+  fn default() -> Self {
+    Self::STANDARD
+  }
+}
+
+impl fmt::Display for BoardConfig {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}x{}", self.width, self.height)
+  }
+}
+
+impl Cycle for BoardConfig {
+  fn options() -> &'static [Self] {
+    &Self::ALL
+  }
+}
+
+impl BoardConfig {
+  /// the narrowest board, which still fits an I piece lying flat.
+  pub const MIN_WIDTH: usize = 4;
+
+  /// the standard 10x20 board.
+  pub const STANDARD: Self = Self {
+    width: GameBoard::COL_COUNT as usize,
+    height: GameBoard::ROW_COUNT as usize,
+    buffer: 20,
+  };
+
+  /// every board which may be picked from the menu, in menu order.
+  pub const ALL: [Self; 4] = [
+    Self::STANDARD,
+    Self { width: 4, ..Self::STANDARD },
+    Self { width: 12, ..Self::STANDARD },
+    Self { height: 40, ..Self::STANDARD },
+  ];
+
+  /// returns true if big mode may be played on this board: it halves
+  /// evenly, and is still at least [`BoardConfig::MIN_WIDTH`] wide.
+  #[must_use]
+  pub fn fits_big(&self) -> bool {
+    self.width.is_multiple_of(2) && self.height.is_multiple_of(2) && self.width / 2 >= Self::MIN_WIDTH
+  }
+}
+
+/// [component][`Component`] of a settled [`Tile`] which is falling into
+/// place, after the rows beneath it were cleared.
+#[derive(Debug, Component)]
+pub struct Settling {
+  /// the height it started falling from, in pixels.
+  from: f32,
+  /// the height it comes to rest at, in pixels.
+  to: f32,
+  timer: Timer,
+}
+
+impl Settling {
+  /// how long it takes to fall into place.
+  pub const DURATION: Duration = Duration::from_millis(150);
+
+  #[must_use]
+  pub fn new(from: f32, to: f32) -> Self {
+    Self {
+      from,
+      to,
+      timer: Timer::new(Self::DURATION, TimerMode::Once),
     }
   }
-  let mut reset_timer = false;
-  for (entity, movable) in &q_piece_blocks {
-    if !movable.can_down && (game_timers.rm_pieces.just_finished() || kb.pressed(KeyCode::Down)) {
-      commands.entity(entity).remove::<Shape>();
-      reset_timer = true;
+}
+
+/// how much of the settled stack the player gets to see.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum StackVisibility {
+  /// the stack is always shown.
+  #[default]
+  Visible,
+  /// tiles fade away a while after they lock.
+  Fading,
+  /// tiles vanish as soon as they lock, and only flash back into view when
+  /// lines are cleared.
+  Invisible,
+}
+
+impl fmt::Display for StackVisibility {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Visible => write!(f, "Visible"),
+      Self::Fading => write!(f, "Fading"),
+      Self::Invisible => write!(f, "Invisible"),
     }
   }
-  if reset_timer {
-    game_timers.rm_pieces.reset();
+}
+
+impl Cycle for StackVisibility {
+  fn options() -> &'static [Self] {
+    &Self::ALL
   }
 }
 
-pub fn check_full_line(
-  mut commands: Commands,
-  mut score: ResMut<Score>,
-  mut query: Query<(Entity, &mut Tile, &mut Transform), Without<Shape>>,
-  audio: Res<Audio>,
-  game_audio: Res<GameAssets>,
-) {
-  let mut y_to_x_set_map: HashMap<i32, HashSet<i32>> = HashMap::new();
-  for (_, block, _) in &query {
-    if let std::collections::hash_map::Entry::Vacant(e) = y_to_x_set_map.entry(block.1) {
-      let mut x_set = HashSet::new();
-      x_set.insert(block.0);
-      e.insert(x_set);
-    } else {
-      let x_set = y_to_x_set_map.get_mut(&block.1).unwrap();
-      x_set.insert(block.0);
+impl StackVisibility {
+  /// every option, in the order they are cycled through in the menu.
+  pub const ALL: [Self; 3] = [Self::Visible, Self::Fading, Self::Invisible];
+  /// how long a tile stays in sight after it locks, while the stack is fading.
+  pub const FADE_DELAY: Duration = Duration::from_secs(3);
+  /// how long a fading tile takes to disappear.
+  pub const FADE_TIME: Duration = Duration::from_secs(1);
+  /// how long the stack stays in sight after a line clear, while it is invisible.
+  pub const REVEAL_TIME: Duration = Duration::from_millis(500);
+  /// how long an invisible tile flashes for.
+  pub const FLASH_TIME: Duration = Duration::from_millis(150);
+
+  /// returns how a tile which just locked fades away, if at all.
+  #[must_use]
+  pub fn on_lock(self) -> Option<Fade> {
+    match self {
+      Self::Visible => None,
+      Self::Fading => Some(Fade::new(Self::FADE_DELAY, Self::FADE_TIME)),
+      Self::Invisible => Some(Fade::new(Duration::ZERO, Self::FLASH_TIME)),
     }
   }
 
-  // identify rows which are completely filled with blocks.
-  let mut full_rows = Vec::new();
-  for (y, x_set) in &y_to_x_set_map {
-    if x_set.len() == GameBoard::COL_COUNT as usize {
-      full_rows.push(y);
+  /// returns how the whole stack fades away again after it is revealed by a
+  /// line clear, if it is revealed at all.
+  #[must_use]
+  pub fn on_clear(self) -> Option<Fade> {
+    match self {
+      Self::Visible | Self::Fading => None,
+      Self::Invisible => Some(Fade::new(Self::REVEAL_TIME, Self::FLASH_TIME)),
     }
   }
+}
 
-  // if no lines to clear, we're done.
-  if full_rows.is_empty() {
-    return;
+/// [component][`Component`] of a settled [`Tile`] which fades out of sight:
+/// it is held in sight for a while, then fades away.
+#[derive(Debug, Clone, Component)]
+pub struct Fade {
+  /// how long the tile is held in sight before it starts to fade.
+  hold: Duration,
+  timer: Timer,
+}
+
+impl Fade {
+  #[must_use]
+  pub fn new(hold: Duration, fade: Duration) -> Self {
+    Self {
+      hold,
+      timer: Timer::new(hold + fade, TimerMode::Once),
+    }
   }
 
-  // otherwise, play audio and update score.
-  game_audio.play_line_clear(&audio);
-  score.update(full_rows.len());
+  /// returns how opaque the tile is now.
+  #[must_use]
+  pub fn alpha(&self) -> f32 {
+    let fading = self.timer.elapsed().saturating_sub(self.hold);
+    let fade = self.timer.duration().saturating_sub(self.hold);
+    if fade.is_zero() {
+      return if self.timer.finished() { 0.0 } else { 1.0 };
+    }
+    1.0 - fading.as_secs_f32() / fade.as_secs_f32()
+  }
+}
 
-  // despawn the blocks in the full lines.
-  let mut despawn_entities = Vec::new();
-  for line_no in &full_rows {
-    let line_no = line_no.clone().to_owned();
-    for (entity, block, _) in &mut query {
-      if block.1 == line_no {
-        despawn_entities.push(entity);
-        commands.entity(entity).despawn();
-      }
+/// [system][`System`] responsible for transitioning to the "game over" state
+/// once the [`Engine`] reports that the game is over. the [`Engine`]'s mode
+/// decides when that is: in some, topping out is only a setback.
+pub fn detect_game_over(
+  mut events: EventReader<EngineEvent>,
+  mut app_state: ResMut<NextState<AppState>>,
+  mut game_state: ResMut<NextState<GameState>>,
+  audio: Res<Audio>,
+  game_audio: Res<GameAssets>,
+) {
+  for event in events.iter() {
+    let EngineEvent::GameOver(outcome) = event else {
+      continue;
+    };
+    if !outcome.is_win() {
+      game_audio.play_gameover(&audio);
     }
+    app_state.set(AppState::GameOver);
+    game_state.set(GameState::Quitting);
   }
+}
 
-  // move down the blocks above the full lines.
-  full_rows.sort();
-  full_rows.reverse();
-  for line_no in &full_rows {
-    for (entity, mut block, mut transform) in &mut query {
-      if !despawn_entities.contains(&entity) && block.1 > *line_no.clone() {
-        debug!(
-          "block ({},{}) fell (line_no={}).",
-          block.0, block.1, line_no
-        );
-        block.1 -= 1;
-        transform.translation = block.translation();
-      }
+/// [system][`System`] which plays the sound effects for [`EngineEvent`]s.
+pub fn play_sounds(
+  mut events: EventReader<EngineEvent>,
+  audio: Res<Audio>,
+  game_audio: Res<GameAssets>,
+) {
+  for event in events.iter() {
+    match event {
+      EngineEvent::Fell | EngineEvent::HardDropped(_) => game_audio.play_drop(&audio),
+      EngineEvent::LinesCleared(_) => game_audio.play_line_clear(&audio),
+      EngineEvent::Countdown(_) => game_audio.play_warning(&audio),
+      EngineEvent::ToppedOut(_) => game_audio.play_gameover(&audio),
+      _ => {}
     }
   }
 }
@@ -0,0 +1,1886 @@
+//! headless rules engine.
+//!
+//! the [`Engine`] owns the matrix, the falling piece, the queue and the score,
+//! and knows nothing about entities or sprites. bevy [systems][`System`] feed
+//! it [`Inputs`] once per frame, through the [`GameEngine`] resource, and
+//! mirror whatever it reports back onto the screen, so the game can be
+//! simulated (or embedded) without a window.
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+  prelude::*,
+  randomizer::Randomizer,
+  mode::{Mode, Outcome, Timing},
+  score::{Clear, ClearKind, Stats, TSpin},
+  timers::Ticker,
+};
+
+/// what the player is asking of the falling piece during a single [`Engine::step`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Inputs {
+  /// is the player holding "left"?
+  pub left: bool,
+  /// is the player holding "right"?
+  pub right: bool,
+  /// is the player holding "soft drop"?
+  pub soft_drop: bool,
+  /// did the player just press "hard drop"?
+  pub hard_drop: bool,
+  /// did the player just press "sonic drop"?
+  pub sonic_drop: bool,
+  /// did the player just press "rotate clockwise"?
+  pub rotate_cw: bool,
+  /// did the player just press "rotate counter-clockwise"?
+  pub rotate_ccw: bool,
+  /// did the player just press "rotate 180°"?
+  pub rotate_180: bool,
+  /// did the player just press "hold"?
+  pub hold: bool,
+  /// did the player just press "undo"? only in modes which allow it.
+  pub undo: bool,
+  /// did the player just press "toggle gravity"? only in modes which allow it.
+  pub toggle_gravity: bool,
+  /// the rotate and hold keys the player is holding down, which are applied
+  /// to a piece as it spawns after the entry delay (IRS and IHS).
+  pub initial: Actions,
+}
+
+impl Inputs {
+  /// returns the rotations and holds which were just pressed.
+  #[must_use]
+  pub fn pressed(&self) -> Actions {
+    Actions {
+      rotate_cw: self.rotate_cw,
+      rotate_ccw: self.rotate_ccw,
+      rotate_180: self.rotate_180,
+      hold: self.hold,
+    }
+  }
+}
+
+/// the rotations and holds which may be applied to a piece as it spawns.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Actions {
+  /// turn the piece clockwise.
+  pub rotate_cw: bool,
+  /// turn the piece counter-clockwise.
+  pub rotate_ccw: bool,
+  /// turn the piece 180°.
+  pub rotate_180: bool,
+  /// hold the piece.
+  pub hold: bool,
+}
+
+impl Actions {
+  /// returns every action in either `self` or `other`.
+  #[must_use]
+  pub fn union(self, other: Self) -> Self {
+    Self {
+      rotate_cw: self.rotate_cw || other.rotate_cw,
+      rotate_ccw: self.rotate_ccw || other.rotate_ccw,
+      rotate_180: self.rotate_180 || other.rotate_180,
+      hold: self.hold || other.hold,
+    }
+  }
+
+  /// returns how many quarter turns clockwise to make, if any. clockwise
+  /// wins over 180°, which wins over counter-clockwise.
+  #[must_use]
+  pub fn quarter_turns(self) -> Option<usize> {
+    [(self.rotate_cw, 1), (self.rotate_180, 2), (self.rotate_ccw, 3)]
+      .into_iter()
+      .find_map(|(pressed, quarter_turns)| pressed.then_some(quarter_turns))
+  }
+}
+
+/// something which happened inside the [`Engine`] during a [`Engine::step`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineEvent {
+  /// a new piece entered the matrix.
+  Spawned(Shape),
+  /// the falling piece moved because the player asked it to.
+  Moved,
+  /// the falling piece moved down one or more rows because of gravity.
+  Fell,
+  /// the falling piece was rotated.
+  Rotated,
+  /// the player soft dropped the falling piece down a row.
+  SoftDropped,
+  /// the player dropped the falling piece this many rows and locked it.
+  HardDropped(usize),
+  /// the player dropped the falling piece this many rows, without locking it.
+  SonicDropped(usize),
+  /// the player held a piece.
+  Held(Shape),
+  /// the falling piece locked into the matrix.
+  Locked(Shape),
+  /// one or more rows were filled, and will be cleared once the line clear delay is over.
+  LinesCleared(usize),
+  /// the full rows (listed from the bottom up) were removed, and the rows above them fell.
+  Collapsed(Vec<usize>),
+  /// this many garbage rows rose from the bottom, pushing the stack up.
+  GarbageRose(usize),
+  /// the player topped out, but the mode forgave it and emptied the matrix.
+  ToppedOut(TopOut),
+  /// the last placement was taken back.
+  Undone,
+  /// a locked piece scored a clear or a T-spin.
+  Cleared(Clear),
+  /// this many whole seconds are left before a timed mode runs out.
+  Countdown(u64),
+  /// the game is over.
+  GameOver(Outcome),
+}
+
+/// the ways a game may be lost by stacking too high.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TopOut {
+  /// a piece spawned overlapping the stack.
+  BlockOut,
+  /// a piece locked entirely above the visible field.
+  LockOut,
+  /// a piece locked partly above the visible field, without clearing a line.
+  /// only when [`Settings::partial_lock_out`] is on.
+  PartialLockOut,
+  /// tiles ended up above the hidden buffer, where the matrix cannot keep them.
+  Overflow,
+}
+
+impl fmt::Display for TopOut {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::BlockOut => write!(f, "Block Out"),
+      Self::LockOut => write!(f, "Lock Out"),
+      Self::PartialLockOut => write!(f, "Partial Lock Out"),
+      Self::Overflow => write!(f, "Top Out"),
+    }
+  }
+}
+
+/// the matrix of settled tiles, where row `0` is the bottom of the board.
+///
+/// each row is a bitmask with one bit per column (bit `0` is the leftmost
+/// column), which keeps collision checks, line detection and row collapse
+/// cheap. the shape of each settled tile is tracked alongside, but only for
+/// drawing.
+///
+/// the matrix is taller than the visible board; pieces spawn into the hidden
+/// buffer rows above it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Board {
+  /// how many columns wide is the matrix?
+  width: usize,
+  /// the occupied cells of each row, bottom row first.
+  rows: Vec<u16>,
+  /// what settled in each cell, bottom row first.
+  cells: Vec<[Option<Cell>; Board::MAX_WIDTH]>,
+}
+
+/// what fills a settled cell of the matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+  /// part of a piece which locked.
+  Piece(Shape),
+  /// part of a garbage row, which rose from the bottom of the matrix.
+  Garbage,
+}
+
+impl fmt::Display for Cell {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Piece(shape) => write!(f, "{shape}"),
+      Self::Garbage => write!(f, "Garbage"),
+    }
+  }
+}
+
+impl Cell {
+  /// the color of garbage, whatever the rotation system.
+  pub const GARBAGE_COLOR: Color = palette::OVERLAY0;
+
+  /// returns the color this cell is drawn in.
+  #[must_use]
+  pub fn color(self, rotation_system: &dyn RotationSystem) -> Color {
+    match self {
+      Self::Piece(shape) => rotation_system.color(shape),
+      Self::Garbage => Self::GARBAGE_COLOR,
+    }
+  }
+}
+
+impl Default for Board {
+  fn default() -> Self {
+    let config = BoardConfig::default();
+    Self::new(config.width, config.height + config.buffer)
+  }
+}
+
+impl Board {
+  /// the widest matrix which fits into a row's bitmask.
+  pub const MAX_WIDTH: usize = u16::BITS as usize;
+
+  /// creates an empty matrix of the given size.
+  ///
+  /// # Panics
+  ///
+  /// if `width` is wider than [`Self::MAX_WIDTH`].
+  #[must_use]
+  pub fn new(width: usize, height: usize) -> Self {
+    assert!(
+      width <= Self::MAX_WIDTH,
+      "board is {width} columns wide, but may be at most {} wide",
+      Self::MAX_WIDTH
+    );
+    Self {
+      width,
+      rows: vec![0; height],
+      cells: vec![[None; Self::MAX_WIDTH]; height],
+    }
+  }
+
+  /// how many columns wide is the matrix?
+  #[must_use]
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  /// how many rows tall is the matrix (including hidden rows)?
+  #[must_use]
+  pub fn height(&self) -> usize {
+    self.rows.len()
+  }
+
+  /// the bitmask of a row which has every column occupied.
+  #[must_use]
+  pub fn full_row(&self) -> u16 {
+    ((1_u32 << self.width) - 1) as u16
+  }
+
+  /// returns the bitmask of occupied cells in the given row (empty above the matrix).
+  #[must_use]
+  pub fn row(&self, y: usize) -> u16 {
+    self.rows.get(y).copied().unwrap_or_default()
+  }
+
+  /// returns what settled at the given position, if anything.
+  #[must_use]
+  pub fn get(&self, x: i32, y: i32) -> Option<Cell> {
+    let x = usize::try_from(x).ok()?;
+    let y = usize::try_from(y).ok()?;
+    *self.cells.get(y)?.get(x)?
+  }
+
+  /// returns true if a tile may occupy the given position. the walls and the
+  /// floor are solid, while everything above the matrix is open.
+  #[must_use]
+  pub fn is_free(&self, x: i32, y: i32) -> bool {
+    let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) else {
+      return false;
+    };
+    x < self.width && self.row(y) & (1 << x) == 0
+  }
+
+  /// returns true if all of the given tiles may be occupied.
+  #[must_use]
+  pub fn fits(&self, blocks: &[Tile; 4]) -> bool {
+    blocks.iter().all(|block| self.is_free(block.0, block.1))
+  }
+
+  /// settles a piece into the matrix.
+  pub fn lock(&mut self, piece: &Tetromino) {
+    for block in piece.blocks {
+      let (Ok(x), Ok(y)) = (usize::try_from(block.0), usize::try_from(block.1)) else {
+        continue;
+      };
+      if x >= self.width || y >= self.height() {
+        continue;
+      }
+      self.rows[y] |= 1 << x;
+      self.cells[y][x] = Some(Cell::Piece(piece.shape));
+    }
+  }
+
+  /// returns the index of every full row, from the bottom up.
+  #[must_use]
+  pub fn full_rows(&self) -> Vec<usize> {
+    let full = self.full_row();
+    (0..self.height()).filter(|y| self.rows[*y] == full).collect()
+  }
+
+  /// removes every full row, moving the rows above them down. returns how
+  /// many rows were removed.
+  pub fn clear_full_rows(&mut self) -> usize {
+    let full = self.full_row();
+    let mut kept = 0;
+    for y in 0..self.height() {
+      if self.rows[y] != full {
+        self.rows[kept] = self.rows[y];
+        self.cells[kept] = self.cells[y];
+        kept += 1;
+      }
+    }
+    self.rows[kept..].fill(0);
+    self.cells[kept..].fill([None; Self::MAX_WIDTH]);
+    self.height() - kept
+  }
+
+  /// inserts a garbage row for each of the given `holes` (bottom row first)
+  /// under the stack, pushing it up. each row is full except for the column
+  /// of its hole; holes past the last column are put in the last column, so
+  /// that no row is ever full. returns true if tiles were pushed off the top
+  /// of the matrix.
+  pub fn insert_garbage(&mut self, holes: &[usize]) -> bool {
+    let count = holes.len().min(self.height());
+    let overflow = self.rows[self.height() - count..].iter().any(|row| *row != 0);
+    self.rows.rotate_right(count);
+    self.cells.rotate_right(count);
+    for (y, &hole) in holes.iter().take(count).enumerate() {
+      let hole = hole.min(self.width - 1);
+      self.rows[y] = self.full_row() & !(1 << hole);
+      self.cells[y] = [None; Self::MAX_WIDTH];
+      for x in (0..self.width).filter(|x| *x != hole) {
+        self.cells[y][x] = Some(Cell::Garbage);
+      }
+    }
+    overflow
+  }
+
+  /// returns true if the given row holds any garbage.
+  #[must_use]
+  pub fn has_garbage(&self, y: usize) -> bool {
+    self
+      .cells
+      .get(y)
+      .is_some_and(|row| row.contains(&Some(Cell::Garbage)))
+  }
+
+  /// how many rows hold any garbage?
+  #[must_use]
+  pub fn garbage_rows(&self) -> usize {
+    (0..self.height()).filter(|y| self.has_garbage(*y)).count()
+  }
+
+  /// how many rows, counting from the floor, contain at least one tile?
+  #[must_use]
+  pub fn stack_height(&self) -> usize {
+    self.rows.iter().rposition(|row| *row != 0).map_or(0, |y| y + 1)
+  }
+
+  /// iterates over every settled tile and what fills it.
+  pub fn tiles(&self) -> impl Iterator<Item = (Tile, Cell)> + '_ {
+    self.cells.iter().enumerate().flat_map(|(y, row)| {
+      row
+        .iter()
+        .enumerate()
+        .filter_map(move |(x, cell)| cell.map(|cell| (Tile(x as i32, y as i32), cell)))
+    })
+  }
+}
+
+/// the game as it was before a piece spawned, to undo back to. the mode
+/// isn't kept, so modes which allow undo keep no state of their own.
+#[derive(Debug, Clone)]
+struct Snapshot {
+  board: Board,
+  queue: VecDeque<Tetromino>,
+  randomizer: Box<dyn Randomizer>,
+  score: Score,
+  stats: Stats,
+  data: GameBoardData,
+}
+
+/// the complete state of a single game.
+#[derive(Debug)]
+pub struct Engine {
+  /// the settings this game was created with.
+  pub settings: Settings,
+  /// the seed the randomizer was created with.
+  pub seed: u64,
+  /// the size of the field, and of its cells on screen.
+  pub geometry: Geometry,
+  /// the settled tiles.
+  pub board: Board,
+  /// the falling piece, if one is in play.
+  pub piece: Option<Tetromino>,
+  /// what the game is doing: dropping a piece, or waiting between pieces.
+  pub phase: PlayPhase,
+  /// the full rows waiting to be removed during [`PlayPhase::LineClear`].
+  pub clearing: Vec<usize>,
+  /// pieces waiting to be spawned.
+  pub queue: Queue,
+  /// deals the pieces which go into the queue.
+  randomizer: Box<dyn Randomizer>,
+  /// decides where the holes in garbage rows go.
+  garbage_rng: StdRng,
+  /// points and lines earned so far.
+  pub score: Score,
+  /// the player's held piece.
+  pub data: GameBoardData,
+  /// gravity, input and lock timers.
+  pub timers: GameTimers,
+  /// how many times the lock delay has been reset since the falling piece
+  /// last reached a new lowest row.
+  lock_resets: usize,
+  /// the lowest row the falling piece's bottom has reached.
+  lowest_row: i32,
+  /// how many quarter turns the last rotation made, and the index of the kick
+  /// it used, if turning was the falling piece's last move.
+  last_kick: Option<(usize, usize)>,
+  /// presses made between pieces, waiting for the next one.
+  buffered: Actions,
+  /// time, pieces and keys played so far.
+  pub stats: Stats,
+  /// the inputs from the last step, to tell when keys are pressed.
+  last_inputs: Inputs,
+  /// what happened while setting up the game, reported by the first step.
+  pending: Vec<EngineEvent>,
+  /// the game as it was before each recent piece spawned, oldest first, to undo back to.
+  history: VecDeque<Snapshot>,
+  /// do pieces fall on their own? only modes which allow it may turn this off.
+  pub gravity: bool,
+  /// the rules which decide when the game is won or lost.
+  mode: Box<dyn Mode>,
+  /// how the game ended, once it has.
+  pub outcome: Option<Outcome>,
+}
+
+impl Default for Engine {
+  fn default() -> Self {
+    Self::new(&Settings::default(), &BoardConfig::default())
+  }
+}
+
+impl Engine {
+  /// how many times moving or rotating a grounded piece may reset its lock
+  /// delay, before it has to fall to a new lowest row.
+  pub const MAX_LOCK_RESETS: usize = 15;
+
+  /// creates a new game with the given settings, on a board of the given size.
+  #[must_use]
+  pub fn new(settings: &Settings, config: &BoardConfig) -> Self {
+    let seed = settings.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let mode = settings.mode.build();
+    let big = settings.big && config.fits_big();
+    let geometry = Geometry::new(config, if big { 2 } else { 1 });
+    // a goal of zero never levels up.
+    let mut score = Score::new(Level(settings.start_level), mode.level_goal().unwrap_or(0));
+    score.line_scale = geometry.scale as u32;
+    let mut engine = Self {
+      settings: settings.clone(),
+      seed,
+      geometry,
+      board: Board::new(
+        geometry.cols,
+        geometry.rows + config.buffer.div_ceil(geometry.scale),
+      ),
+      piece: None,
+      phase: PlayPhase::Falling,
+      clearing: Vec::new(),
+      queue: Queue(VecDeque::new()),
+      randomizer: settings.randomizer.build(seed),
+      // a different stream from the randomizer's, so garbage does not change the pieces.
+      garbage_rng: StdRng::seed_from_u64(seed.rotate_left(32)),
+      score,
+      data: GameBoardData::default(),
+      timers: GameTimers::new(settings),
+      lock_resets: 0,
+      lowest_row: i32::MAX,
+      last_kick: None,
+      buffered: Actions::default(),
+      stats: Stats::default(),
+      last_inputs: Inputs::default(),
+      pending: Vec::new(),
+      history: VecDeque::new(),
+      gravity: true,
+      mode,
+      outcome: None,
+    };
+    engine.fill_queue();
+    let mut events = Vec::new();
+    engine.top_up_garbage(&mut events);
+    engine.pending = events;
+    engine
+  }
+
+  /// discards the current game and starts a fresh one with the given settings.
+  pub fn reset(&mut self, settings: &Settings, config: &BoardConfig) {
+    *self = Self::new(settings, config);
+  }
+
+  /// each whole second left from this down to 1 is announced as a timed mode
+  /// runs out.
+  pub const COUNTDOWN: Duration = Duration::from_secs(10);
+
+  /// advances the game by `dt`, applying the player's `inputs`, and returns
+  /// everything that happened along the way.
+  pub fn step(&mut self, inputs: Inputs, dt: Duration) -> Vec<EngineEvent> {
+    let mut events = std::mem::take(&mut self.pending);
+    if self.is_over() {
+      return events;
+    }
+    let time_left = self.mode.time_left(&self.stats);
+    self.stats.time += dt;
+    self.stats.keys += self.count_presses(inputs);
+    self.advance(inputs, dt, &mut events);
+    if self.is_over() {
+      return events;
+    }
+
+    if let (Some(before), Some(after)) = (time_left, self.mode.time_left(&self.stats)) {
+      let seconds = |time: Duration| time.as_millis().div_ceil(1000) as u64;
+      let (before, after) = (seconds(before), seconds(after));
+      if before != after && (1..=Self::COUNTDOWN.as_secs()).contains(&after) {
+        events.push(EngineEvent::Countdown(after));
+      }
+    }
+    if let Some(outcome) = self.mode.outcome(&self.score, &self.stats) {
+      self.end(outcome, &mut events);
+    }
+    events
+  }
+
+  /// has the game ended?
+  #[must_use]
+  pub fn is_over(&self) -> bool {
+    self.outcome.is_some()
+  }
+
+  /// returns the rules for how this game is won or lost.
+  #[must_use]
+  pub fn mode(&self) -> &dyn Mode {
+    self.mode.as_ref()
+  }
+
+  /// returns how fast the game is played right now.
+  #[must_use]
+  pub fn timing(&self) -> Timing {
+    self.mode.timing(&self.score, &self.settings)
+  }
+
+  /// advances the game by `dt`, applying the player's `inputs`.
+  fn advance(&mut self, inputs: Inputs, dt: Duration, events: &mut Vec<EngineEvent>) {
+    if inputs.toggle_gravity {
+      self.toggle_gravity();
+    }
+    if inputs.undo && self.undo() {
+      events.push(EngineEvent::Undone);
+      return;
+    }
+    // shifting keeps charging between pieces.
+    let shifts = self.timers.shift.tick(
+      inputs.left,
+      inputs.right,
+      dt,
+      self.settings.das,
+      self.settings.arr,
+    );
+    // garbage waits for cleared rows to be removed, so they stay where they are.
+    if self.phase != PlayPhase::LineClear {
+      let rising = self.timers.garbage.as_mut().map_or(0, |timer| {
+        timer.tick(dt);
+        timer.times_finished_this_tick() as usize
+      });
+      if rising > 0 {
+        self.rise_garbage(rising, events);
+        if self.is_over() {
+          return;
+        }
+      }
+    }
+    let mut initial = Actions::default();
+    if self.phase != PlayPhase::Falling {
+      self.buffered = self.buffered.union(inputs.pressed());
+      self.timers.delay.tick(dt);
+      if !self.timers.delay.finished() {
+        return;
+      }
+      if self.phase == PlayPhase::LineClear {
+        self.collapse(events);
+        if self.phase != PlayPhase::Falling {
+          return;
+        }
+      }
+      self.phase = PlayPhase::Falling;
+      // whatever was pressed during the delay, or is still held, applies as
+      // the next piece spawns, rather than once it is in play.
+      initial = std::mem::take(&mut self.buffered).union(inputs.initial);
+    }
+    let mut inputs = inputs;
+    if self.piece.is_none() {
+      self.record();
+      self.spawn_next_piece(initial, events);
+      if initial != Actions::default() {
+        // this step's presses were spent on the spawning piece.
+        inputs = Inputs {
+          rotate_cw: false,
+          rotate_ccw: false,
+          rotate_180: false,
+          hold: false,
+          ..inputs
+        };
+      }
+      if self.is_over() {
+        return;
+      }
+    }
+
+    let timing = self.timing();
+    self.timers.auto.set_duration(timing.fall_interval());
+    self.timers.auto.tick(dt);
+
+    for (pressed, quarter_turns) in [
+      (inputs.rotate_cw, 1),
+      (inputs.rotate_180, 2),
+      (inputs.rotate_ccw, 3),
+    ] {
+      if pressed && self.rotate(quarter_turns) {
+        events.push(EngineEvent::Rotated);
+      }
+    }
+
+    if inputs.hard_drop {
+      let rows = self.drop_to_ghost();
+      self.score.hard_drop(rows);
+      events.push(EngineEvent::HardDropped(rows));
+      self.lock_piece(events);
+      return;
+    }
+    if inputs.sonic_drop {
+      let rows = self.drop_to_ghost();
+      events.push(EngineEvent::SonicDropped(rows));
+    }
+
+    let mut fell = false;
+    let falls = if self.gravity {
+      self.timers.auto.times_finished_this_tick()
+    } else {
+      0
+    };
+    for _ in 0..falls {
+      if !self.try_move(0, -1) {
+        break;
+      }
+      fell = true;
+    }
+    if fell {
+      events.push(EngineEvent::Fell);
+    }
+
+    let direction = self.timers.shift.direction;
+    for _ in 0..shifts.min(self.board.width()) {
+      if !self.try_move(direction, 0) {
+        break;
+      }
+      events.push(EngineEvent::Moved);
+    }
+
+    self.soft_drop(inputs.soft_drop, dt, events);
+
+    if inputs.hold {
+      self.hold(Actions::default(), events);
+    }
+
+    // at 20G, pieces never hang in the air, even right after spawning or moving.
+    if self.gravity && timing.is_instant() && self.drop_to_ghost() > 0 {
+      events.push(EngineEvent::Fell);
+    }
+
+    self.lock_if_grounded(dt, events);
+  }
+
+  /// returns how many keys were pressed since the last step.
+  fn count_presses(&mut self, inputs: Inputs) -> u32 {
+    let last = std::mem::replace(&mut self.last_inputs, inputs);
+    [
+      inputs.left && !last.left,
+      inputs.right && !last.right,
+      inputs.soft_drop && !last.soft_drop,
+      inputs.hard_drop,
+      inputs.sonic_drop,
+      inputs.rotate_cw,
+      inputs.rotate_ccw,
+      inputs.rotate_180,
+      inputs.hold,
+    ]
+    .into_iter()
+    .map(u32::from)
+    .sum()
+  }
+
+  /// returns the rules for how pieces in this game spawn and turn.
+  #[must_use]
+  pub fn rotation_system(&self) -> &'static dyn RotationSystem {
+    self.settings.rotation_system.system()
+  }
+
+  /// returns the upcoming pieces the player may see, soonest first.
+  pub fn preview(&self) -> impl Iterator<Item = &Tetromino> {
+    self.queue.0.iter().take(self.settings.preview_count)
+  }
+
+  /// returns where the falling piece would land if it dropped straight down.
+  #[must_use]
+  pub fn ghost(&self) -> Option<Tetromino> {
+    let mut ghost = self.piece.clone()?;
+    loop {
+      let dropped = ghost.shifted(0, -1);
+      if !self.board.fits(&dropped.blocks) {
+        return Some(ghost);
+      }
+      ghost = dropped;
+    }
+  }
+
+  /// tops the queue up with pieces from the randomizer.
+  fn fill_queue(&mut self) {
+    let system = self.rotation_system();
+    let len = (Shape::VARIANTS as usize).max(self.settings.preview_count);
+    while self.queue.0.len() < len {
+      let shape = self.randomizer.next();
+      self.queue.0.push_back(Tetromino::spawn(shape, system, &self.geometry));
+    }
+  }
+
+  /// pops the next piece off of the queue (refilling it as necessary) and
+  /// puts it into play, whether it follows a lock or a first hold.
+  fn spawn_next_piece(&mut self, initial: Actions, events: &mut Vec<EngineEvent>) {
+    let Some(piece) = self.queue.0.pop_front() else {
+      return;
+    };
+    self.mode.on_spawn(&self.stats);
+    self.fill_queue();
+    self.enter(piece, initial, events);
+  }
+
+  /// puts a spawning piece into play, applying the `initial` hold (IHS) or
+  /// rotation (IRS). if there is no room for it, the game is over (block out).
+  fn enter(&mut self, piece: Tetromino, initial: Actions, events: &mut Vec<EngineEvent>) {
+    events.push(EngineEvent::Spawned(piece.shape));
+    if initial.hold && !self.data.held {
+      self.place(piece);
+      self.hold(Actions { hold: false, ..initial }, events);
+      return;
+    }
+    // a turn which does not fit is ignored, rather than kicked.
+    let system = self.rotation_system();
+    let turned = initial
+      .quarter_turns()
+      .map(|quarter_turns| piece.rotated(piece.rotation.turned(quarter_turns), system))
+      .filter(|turned| self.board.fits(&turned.blocks));
+    if turned.is_some() {
+      events.push(EngineEvent::Rotated);
+    }
+    let piece = turned.unwrap_or(piece);
+    if !self.board.fits(&piece.blocks) {
+      self.top_out(TopOut::BlockOut, events);
+    }
+    self.place(piece);
+  }
+
+  /// puts a freshly spawned piece into play, with a fresh lock delay.
+  fn place(&mut self, piece: Tetromino) {
+    self.lowest_row = Self::bottom(&piece);
+    self.lock_resets = 0;
+    self.last_kick = None;
+    self.timers.lock.set_duration(self.timing().lock_delay);
+    self.timers.lock.reset();
+    self.piece = Some(piece);
+  }
+
+  /// returns the row of a piece's lowest block.
+  fn bottom(piece: &Tetromino) -> i32 {
+    piece.blocks.iter().map(|block| block.1).min().unwrap_or_default()
+  }
+
+  /// resets the lock delay after the falling piece moved or turned. this is
+  /// only allowed [`Self::MAX_LOCK_RESETS`] times, unless the piece has
+  /// reached a new lowest row since. moves in the air, before the lock delay
+  /// has started, don't count.
+  fn reset_lock_delay(&mut self) {
+    let Some(piece) = &self.piece else {
+      return;
+    };
+    let bottom = Self::bottom(piece);
+    let grounded = !self.board.fits(&piece.shifted(0, -1).blocks);
+    if bottom < self.lowest_row {
+      self.lowest_row = bottom;
+      self.lock_resets = 0;
+    } else if !grounded && self.timers.lock.elapsed().is_zero() {
+      return;
+    } else if self.lock_resets < Self::MAX_LOCK_RESETS {
+      self.lock_resets += 1;
+    } else {
+      return;
+    }
+    self.timers.lock.reset();
+  }
+
+  /// moves the falling piece, if it fits at its new position.
+  fn try_move(&mut self, delta_x: i32, delta_y: i32) -> bool {
+    let Some(piece) = self.piece.as_mut() else {
+      return false;
+    };
+    let moved = piece.shifted(delta_x, delta_y);
+    if !self.board.fits(&moved.blocks) {
+      return false;
+    }
+    *piece = moved;
+    self.last_kick = None;
+    self.reset_lock_delay();
+    true
+  }
+
+  /// drops the falling piece [`Settings::sdf`] times faster than gravity while
+  /// soft drop is held, starting as soon as it is pressed.
+  fn soft_drop(&mut self, held: bool, dt: Duration, events: &mut Vec<EngineEvent>) {
+    let timer = &mut self.timers.soft_drop;
+    if !held {
+      timer.set_elapsed(timer.duration());
+      return;
+    }
+    timer.set_duration(self.timers.auto.duration() / self.settings.sdf.max(1));
+    timer.tick(dt);
+    for _ in 0..self.timers.soft_drop.times_finished_this_tick() {
+      if !self.try_move(0, -1) {
+        break;
+      }
+      self.score.soft_drop(1);
+      events.push(EngineEvent::SoftDropped);
+    }
+  }
+
+  /// moves the falling piece straight down as far as it will go, returning
+  /// how many rows it fell.
+  fn drop_to_ghost(&mut self) -> usize {
+    let mut rows = 0;
+    while self.try_move(0, -1) {
+      rows += 1;
+    }
+    rows
+  }
+
+  /// turns the falling piece clockwise `quarter_turns` times, trying each of
+  /// the rotation system's kicks in order until one fits.
+  fn rotate(&mut self, quarter_turns: usize) -> bool {
+    let system = self.rotation_system();
+    let Some(piece) = self.piece.as_mut() else {
+      return false;
+    };
+    let rotated = piece.rotated(piece.rotation.turned(quarter_turns), system);
+    for (kick, &(delta_x, delta_y)) in system.kicks(&self.board, piece, &rotated).iter().enumerate() {
+      let kicked = rotated.shifted(delta_x, delta_y);
+      if self.board.fits(&kicked.blocks) {
+        *piece = kicked;
+        self.last_kick = Some((quarter_turns, kick));
+        self.reset_lock_delay();
+        return true;
+      }
+    }
+    false
+  }
+
+  /// swaps the falling piece with the held one (or with the next piece, if
+  /// nothing is held yet), once per locked piece. the piece swapped in spawns
+  /// with the `initial` rotation.
+  fn hold(&mut self, initial: Actions, events: &mut Vec<EngineEvent>) {
+    // bail if player already held a piece this round.
+    if self.data.held {
+      return;
+    }
+    let Some(piece) = self.piece.take() else {
+      return;
+    };
+    let swapped = self.data.held_piece;
+    events.push(EngineEvent::Held(piece.shape));
+    self.data.hold(piece.shape);
+    match swapped {
+      Some(shape) => {
+        let piece = Tetromino::spawn(shape, self.rotation_system(), &self.geometry);
+        self.enter(piece, initial, events);
+      }
+      None => self.spawn_next_piece(initial, events),
+    }
+  }
+
+  /// locks the falling piece once it has been resting on the stack for the
+  /// lock delay, or as soon as it touches down with no lock resets left.
+  fn lock_if_grounded(&mut self, dt: Duration, events: &mut Vec<EngineEvent>) {
+    let Some(piece) = &self.piece else {
+      return;
+    };
+    if self.board.fits(&piece.shifted(0, -1).blocks) {
+      return;
+    }
+    self.timers.lock.tick(dt);
+    if self.timers.lock.finished() || self.lock_resets >= Self::MAX_LOCK_RESETS {
+      self.lock_piece(events);
+    }
+  }
+
+  /// returns how `piece` was spun into place, if it is a T which was turned
+  /// (rather than moved) last, using the 3-corner rule: three of the four
+  /// corners around its centre must be filled, and unless both in front of
+  /// it are (or the rotation system says the kick makes it full) it is a mini.
+  fn t_spin(&self, piece: &Tetromino) -> Option<TSpin> {
+    if piece.shape != Shape::T {
+      return None;
+    }
+    let (quarter_turns, kick) = self.last_kick?;
+    let blocks = piece.blocks;
+    // the centre of a T is the block touching the other three.
+    let touching = |a: &Tile, b: &Tile| (a.0 - b.0).abs() + (a.1 - b.1).abs() == 1;
+    let centre = *blocks
+      .iter()
+      .find(|a| blocks.iter().filter(|b| touching(a, b)).count() == 3)?;
+    // the T points away from the one side of its centre without a block.
+    let (back_x, back_y) = [(0, 1), (1, 0), (0, -1), (-1, 0)]
+      .into_iter()
+      .find(|(dx, dy)| !blocks.contains(&Tile(centre.0 + dx, centre.1 + dy)))?;
+    let filled = |forward: i32, side: i32| {
+      let x = centre.0 - back_x * forward + back_y * side;
+      let y = centre.1 - back_y * forward + back_x * side;
+      usize::from(!self.board.is_free(x, y))
+    };
+    let front = filled(1, 1) + filled(1, -1);
+    let back = filled(-1, 1) + filled(-1, -1);
+    if front + back < 3 {
+      None
+    } else if front == 2 || self.rotation_system().is_full_t_spin_kick(quarter_turns, kick) {
+      Some(TSpin::Full)
+    } else {
+      Some(TSpin::Mini)
+    }
+  }
+
+  /// settles the falling piece into the matrix, then clears full rows and
+  /// checks whether the game is over.
+  fn lock_piece(&mut self, events: &mut Vec<EngineEvent>) {
+    let Some(piece) = self.piece.take() else {
+      return;
+    };
+    let t_spin = self.t_spin(&piece);
+    self.board.lock(&piece);
+    events.push(EngineEvent::Locked(piece.shape));
+    // player may hold a piece again.
+    self.data.held = false;
+
+    let visible = self.geometry.rows as i32;
+    let above = piece.blocks.iter().filter(|block| block.1 >= visible).count();
+    if above == piece.blocks.len() {
+      self.top_out(TopOut::LockOut, events);
+      return;
+    }
+    if piece.blocks.iter().any(|block| block.1 >= self.board.height() as i32) {
+      self.top_out(TopOut::Overflow, events);
+      return;
+    }
+
+    let full = self.board.full_rows();
+    let cleared = full.len();
+    if self.settings.partial_lock_out && above > 0 && cleared == 0 {
+      self.top_out(TopOut::PartialLockOut, events);
+      return;
+    }
+    if cleared > 0 {
+      events.push(EngineEvent::LinesCleared(cleared));
+    }
+    let perfect_clear =
+      cleared > 0 && self.board.tiles().all(|(tile, _)| full.contains(&(tile.1 as usize)));
+    let clear = self.score.update(ClearKind::new(cleared, t_spin), perfect_clear);
+    if clear.kind != ClearKind::Lines(0) {
+      events.push(EngineEvent::Cleared(clear));
+    }
+    self.stats.pieces += 1;
+    self.stats.split(self.score.lines);
+    let garbage = full.iter().filter(|y| self.board.has_garbage(**y)).count();
+    self.stats.garbage_cleared += (garbage * self.geometry.scale) as u32;
+    self.mode.on_lock(cleared * self.geometry.scale, perfect_clear, &self.stats);
+
+    self.clearing = full;
+    let line_clear_delay = self.timing().line_clear_delay;
+    if cleared > 0 && !line_clear_delay.is_zero() {
+      self.wait(PlayPhase::LineClear, line_clear_delay);
+    } else {
+      self.collapse(events);
+    }
+  }
+
+  /// removes the rows being cleared, then waits for the entry delay before
+  /// the next piece.
+  fn collapse(&mut self, events: &mut Vec<EngineEvent>) {
+    if !self.clearing.is_empty() {
+      self.board.clear_full_rows();
+      events.push(EngineEvent::Collapsed(std::mem::take(&mut self.clearing)));
+      self.top_up_garbage(events);
+      if self.is_over() {
+        return;
+      }
+    }
+    let are = self.timing().are;
+    if are.is_zero() {
+      self.phase = PlayPhase::Falling;
+    } else {
+      self.wait(PlayPhase::Entry, are);
+    }
+  }
+
+  /// inserts a garbage row for each of the given `holes` (bottom row first)
+  /// under the stack, pushing it up, and the falling piece with it if they
+  /// would overlap. tiles pushed off the top of the matrix top the player out.
+  /// see [`Board::insert_garbage`] for holes past the last column.
+  pub fn add_garbage(&mut self, holes: &[usize], events: &mut Vec<EngineEvent>) {
+    if holes.is_empty() {
+      return;
+    }
+    let overflow = self.board.insert_garbage(holes);
+    events.push(EngineEvent::GarbageRose(holes.len()));
+    if let Some(piece) = self.piece.as_mut() {
+      for _ in 0..holes.len() {
+        if self.board.fits(&piece.blocks) {
+          break;
+        }
+        *piece = piece.shifted(0, 1);
+      }
+    }
+    if overflow {
+      self.top_out(TopOut::Overflow, events);
+    }
+  }
+
+  /// raises `rows` garbage rows, each with a hole in a random column.
+  fn rise_garbage(&mut self, rows: usize, events: &mut Vec<EngineEvent>) {
+    let holes: Vec<usize> = (0..rows)
+      .map(|_| self.garbage_rng.gen_range(0..self.board.width()))
+      .collect();
+    self.add_garbage(&holes, events);
+  }
+
+  /// raises as many garbage rows as the mode is missing from the matrix, up
+  /// to half of the visible field.
+  fn top_up_garbage(&mut self, events: &mut Vec<EngineEvent>) {
+    let missing = self
+      .mode
+      .garbage_rows(&self.stats)
+      .div_ceil(self.geometry.scale)
+      .min(self.geometry.rows / 2)
+      .saturating_sub(self.board.garbage_rows());
+    if missing > 0 {
+      self.rise_garbage(missing, events);
+    }
+  }
+
+  /// ends the game because the player topped out, unless the mode forgives
+  /// it, in which case the matrix is emptied and play goes on.
+  fn top_out(&mut self, top_out: TopOut, events: &mut Vec<EngineEvent>) {
+    if !self.mode.forgives_top_out() {
+      self.end(Outcome::ToppedOut(top_out), events);
+      return;
+    }
+    self.board = Board::new(self.board.width(), self.board.height());
+    self.clearing.clear();
+    events.push(EngineEvent::ToppedOut(top_out));
+  }
+
+  /// remembers the game as it is before the next piece spawns, so the
+  /// placement may be undone, in modes which allow it.
+  fn record(&mut self) {
+    let depth = self.mode.undo_depth();
+    if depth == 0 {
+      return;
+    }
+    // the snapshot before the piece in play, and one for each placement before it.
+    while self.history.len() > depth {
+      self.history.pop_front();
+    }
+    self.history.push_back(Snapshot {
+      board: self.board.clone(),
+      queue: self.queue.0.clone(),
+      randomizer: self.randomizer.clone(),
+      score: self.score.clone(),
+      stats: self.stats.clone(),
+      data: self.data,
+    });
+  }
+
+  /// takes back the last piece placed, so it spawns again, along with the
+  /// pieces, keys and lines it counted for. the clock keeps running. returns
+  /// false if there is nothing to undo.
+  pub fn undo(&mut self) -> bool {
+    let placed = self.history.len().saturating_sub(usize::from(self.piece.is_some()));
+    if placed == 0 {
+      return false;
+    }
+    self.history.truncate(placed);
+    let Some(snapshot) = self.history.pop_back() else {
+      return false;
+    };
+    self.board = snapshot.board;
+    self.queue.0 = snapshot.queue;
+    self.randomizer = snapshot.randomizer;
+    self.score = snapshot.score;
+    self.stats = Stats {
+      time: self.stats.time,
+      ..snapshot.stats
+    };
+    self.data = snapshot.data;
+    self.piece = None;
+    self.clearing.clear();
+    self.buffered = Actions::default();
+    self.phase = PlayPhase::Falling;
+    true
+  }
+
+  /// turns gravity on or off, in modes which allow it. returns whether
+  /// pieces now fall on their own.
+  pub fn toggle_gravity(&mut self) -> bool {
+    if self.mode.can_toggle_gravity() {
+      self.gravity = !self.gravity;
+    }
+    self.gravity
+  }
+
+  /// ends the game.
+  fn end(&mut self, outcome: Outcome, events: &mut Vec<EngineEvent>) {
+    self.outcome = Some(outcome);
+    events.push(EngineEvent::GameOver(outcome));
+  }
+
+  /// holds play in `phase` for `delay`.
+  fn wait(&mut self, phase: PlayPhase, delay: Duration) {
+    self.phase = phase;
+    self.timers.delay = Ticker::once(delay);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::mode::GameMode;
+
+  /// starts a game without entry or line clear delays, so that each step
+  /// does as little as possible.
+  fn engine() -> Engine {
+    let settings = Settings {
+      seed: Some(7),
+      are: Duration::ZERO,
+      line_clear_delay: Duration::ZERO,
+      ..default()
+    };
+    Engine::new(&settings, &BoardConfig::default())
+  }
+
+  /// steps the engine by `dt` without pressing anything.
+  fn idle(engine: &mut Engine, dt: Duration) -> Vec<EngineEvent> {
+    engine.step(Inputs::default(), dt)
+  }
+
+  /// fills row `y` of the matrix, except for the columns in `holes`.
+  fn fill(engine: &mut Engine, y: i32, holes: std::ops::Range<i32>) {
+    let mut filler = Tetromino::spawn(Shape::O, engine.rotation_system(), &engine.geometry);
+    for x in (0..engine.board.width() as i32).filter(|x| !holes.contains(x)) {
+      filler.blocks = [Tile(x, y); 4];
+      engine.board.lock(&filler);
+    }
+  }
+
+  #[test]
+  fn gravity_moves_the_piece_down_every_fall_interval() {
+    let mut engine = engine();
+    let events = idle(&mut engine, Duration::ZERO);
+    assert!(matches!(events[0], EngineEvent::Spawned(_)));
+    let before = engine.piece.clone().unwrap();
+
+    let interval = engine.timing().fall_interval();
+    assert!(!idle(&mut engine, interval / 2).contains(&EngineEvent::Fell));
+    assert_eq!(engine.piece.as_ref().unwrap().blocks, before.blocks);
+    assert!(idle(&mut engine, interval / 2).contains(&EngineEvent::Fell));
+    assert_eq!(engine.piece.unwrap().blocks, before.shifted(0, -1).blocks);
+  }
+
+  #[test]
+  fn grounded_piece_locks_after_the_lock_delay() {
+    let mut engine = engine();
+    idle(&mut engine, Duration::ZERO);
+    let shape = engine.piece.as_ref().unwrap().shape;
+    let sonic = Inputs {
+      sonic_drop: true,
+      ..default()
+    };
+    assert!(matches!(engine.step(sonic, Duration::ZERO)[0], EngineEvent::SonicDropped(_)));
+
+    let lock_delay = engine.timing().lock_delay;
+    let events = idle(&mut engine, lock_delay - Duration::from_millis(1));
+    assert!(!events.contains(&EngineEvent::Locked(shape)));
+    assert!(engine.piece.is_some());
+    let events = idle(&mut engine, Duration::from_millis(1));
+    assert!(events.contains(&EngineEvent::Locked(shape)));
+    assert!(engine.piece.is_none());
+  }
+
+  /// steps the engine once with `inputs` pressed, and once more to release
+  /// them, and returns what happened.
+  fn tap(engine: &mut Engine, inputs: Inputs) -> Vec<EngineEvent> {
+    let mut events = engine.step(inputs, Duration::ZERO);
+    events.extend(idle(engine, Duration::ZERO));
+    events
+  }
+
+  /// presses left, right or sonic drop.
+  fn left() -> Inputs {
+    Inputs {
+      left: true,
+      ..default()
+    }
+  }
+
+  fn right() -> Inputs {
+    Inputs {
+      right: true,
+      ..default()
+    }
+  }
+
+  fn sonic() -> Inputs {
+    Inputs {
+      sonic_drop: true,
+      ..default()
+    }
+  }
+
+  #[test]
+  fn lock_delay_starts_over_after_each_grounded_move() {
+    assert_eq!(Settings::default().lock_delay, Duration::from_millis(500));
+    let mut engine = engine();
+    idle(&mut engine, Duration::ZERO);
+    tap(&mut engine, sonic());
+    idle(&mut engine, Duration::from_millis(400));
+    tap(&mut engine, left());
+    assert!(idle(&mut engine, Duration::from_millis(499)).is_empty());
+    assert!(engine.piece.is_some());
+    let events = idle(&mut engine, Duration::from_millis(1));
+    assert!(matches!(events[0], EngineEvent::Locked(_)));
+  }
+
+  #[test]
+  fn grounded_piece_locks_after_fifteen_lock_resets() {
+    let mut engine = engine();
+    idle(&mut engine, Duration::ZERO);
+    tap(&mut engine, sonic());
+    for resets in 1..=Engine::MAX_LOCK_RESETS {
+      let events = tap(&mut engine, if resets % 2 == 1 { left() } else { right() });
+      let locked = events.iter().any(|event| matches!(event, EngineEvent::Locked(_)));
+      assert_eq!(locked, resets == Engine::MAX_LOCK_RESETS, "after {resets} resets");
+    }
+  }
+
+  #[test]
+  fn stepping_down_a_row_refills_the_lock_resets() {
+    let mut engine = engine();
+    idle(&mut engine, Duration::ZERO);
+    // a ledge over columns 5 to 9, with an O resting on its left end.
+    fill(&mut engine, 0, 0..5);
+    let piece = Tetromino::new(Shape::O, Rotation::Zero, Tile(0, 0), engine.rotation_system());
+    let column = piece.blocks.iter().map(|block| block.0).min().unwrap();
+    engine.place(piece.shifted(5 - column, 1 - Engine::bottom(&piece)));
+    for resets in 1..=10 {
+      tap(&mut engine, if resets % 2 == 1 { right() } else { left() });
+    }
+    assert_eq!(engine.lock_resets, 10);
+    // half on the ledge, then off of it.
+    tap(&mut engine, left());
+    tap(&mut engine, left());
+    assert_eq!(engine.lock_resets, 11);
+    tap(&mut engine, sonic());
+    assert_eq!(engine.lock_resets, 0);
+    for resets in 1..Engine::MAX_LOCK_RESETS {
+      tap(&mut engine, if resets % 2 == 1 { left() } else { right() });
+    }
+    assert!(engine.piece.is_some());
+  }
+
+  #[test]
+  fn moves_in_the_air_spend_no_lock_resets() {
+    let settings = Settings {
+      seed: Some(7),
+      arr: Duration::ZERO,
+      ..default()
+    };
+    let mut engine = Engine::new(&settings, &BoardConfig::default());
+    idle(&mut engine, Duration::ZERO);
+    for inputs in [left(), right(), left()] {
+      engine.step(inputs, Duration::ZERO);
+      engine.step(inputs, settings.das);
+    }
+    assert_eq!(engine.lock_resets, 0);
+  }
+
+  #[test]
+  fn soft_drop_falls_sdf_times_as_fast_as_gravity() {
+    let mut engine = engine();
+    idle(&mut engine, Duration::ZERO);
+    let before = engine.piece.clone().unwrap();
+    let interval = engine.timing().fall_interval() / engine.settings.sdf;
+    let soft = Inputs {
+      soft_drop: true,
+      ..default()
+    };
+    // the first row drops as soon as it is pressed.
+    assert_eq!(engine.step(soft, Duration::ZERO), [EngineEvent::SoftDropped]);
+    assert!(engine.step(soft, interval - Duration::from_millis(1)).is_empty());
+    assert_eq!(engine.step(soft, Duration::from_millis(1)), [EngineEvent::SoftDropped]);
+    let events = engine.step(soft, interval * 3);
+    assert_eq!(events, vec![EngineEvent::SoftDropped; 3]);
+    assert_eq!(engine.piece.unwrap().blocks, before.shifted(0, -5).blocks);
+  }
+
+  #[test]
+  fn charged_shift_carries_over_to_the_next_piece() {
+    let settings = Settings {
+      seed: Some(7),
+      arr: Duration::ZERO,
+      are: Duration::ZERO,
+      ..default()
+    };
+    let mut engine = Engine::new(&settings, &BoardConfig::default());
+    idle(&mut engine, Duration::ZERO);
+    engine.step(right(), Duration::ZERO);
+    engine.step(right(), settings.das);
+    let hard = Inputs {
+      hard_drop: true,
+      right: true,
+      ..default()
+    };
+    engine.step(hard, Duration::ZERO);
+    // the next piece spawns and shifts straight to the wall.
+    let events = engine.step(right(), Duration::ZERO);
+    assert!(matches!(events[0], EngineEvent::Spawned(_)));
+    assert!(events.contains(&EngineEvent::Moved));
+    let piece = engine.piece.unwrap();
+    let right_edge = piece.blocks.iter().map(|block| block.0).max().unwrap();
+    assert_eq!(right_edge, engine.board.width() as i32 - 1);
+  }
+
+  #[test]
+  fn pieces_dealt_by_a_first_hold_count_as_spawns() {
+    let settings = Settings {
+      seed: Some(7),
+      mode: GameMode::Master,
+      ..default()
+    };
+    let mut engine = Engine::new(&settings, &BoardConfig::default());
+    idle(&mut engine, Duration::ZERO);
+    assert_eq!(engine.mode().progress().as_deref(), Some("Level: 1/100\n"));
+    let hold = Inputs {
+      hold: true,
+      ..default()
+    };
+    let events = engine.step(hold, Duration::ZERO);
+    assert!(matches!(events[..], [EngineEvent::Held(_), EngineEvent::Spawned(_), ..]));
+    assert_eq!(engine.mode().progress().as_deref(), Some("Level: 2/100\n"));
+  }
+
+  #[test]
+  fn full_rows_are_cleared_and_the_rows_above_collapse() {
+    let mut engine = engine();
+    idle(&mut engine, Duration::ZERO);
+    // a flat I spawns over columns 3 to 6.
+    fill(&mut engine, 0, 3..7);
+    fill(&mut engine, 1, 1..10);
+    engine.piece = Some(Tetromino::spawn(Shape::I, engine.rotation_system(), &engine.geometry));
+
+    let hard = Inputs {
+      hard_drop: true,
+      ..default()
+    };
+    let events = engine.step(hard, Duration::ZERO);
+    assert!(events.contains(&EngineEvent::Locked(Shape::I)));
+    assert!(events.contains(&EngineEvent::LinesCleared(1)));
+    assert!(events.contains(&EngineEvent::Collapsed(vec![0])));
+    assert_eq!(engine.score.lines, 1);
+    // the lone tile from row 1 fell into row 0.
+    assert_eq!(engine.board.stack_height(), 1);
+    assert_eq!(engine.board.row(0), 1);
+  }
+
+  #[test]
+  fn countdown_announces_the_last_ten_seconds() {
+    let settings = Settings {
+      seed: Some(7),
+      mode: GameMode::Ultra {
+        time: Duration::from_secs(120),
+      },
+      ..default()
+    };
+    let mut engine = Engine::new(&settings, &BoardConfig::default());
+    let mut countdown = Vec::new();
+    while !engine.is_over() {
+      for event in idle(&mut engine, Duration::from_millis(300)) {
+        if let EngineEvent::Countdown(seconds) = event {
+          countdown.push(seconds);
+        }
+      }
+    }
+    assert_eq!(countdown, [10, 9, 8, 7, 6, 5, 4, 3, 2, 1]);
+  }
+
+  #[test]
+  fn garbage_holes_past_the_last_column_go_in_the_last_column() {
+    let mut board = Board::new(10, 20);
+    assert!(!board.insert_garbage(&[9, 10, 15, 16, usize::MAX]));
+    for y in 0..5 {
+      assert_eq!(board.row(y), board.full_row() & !(1 << 9), "row {y}");
+    }
+    assert!(board.full_rows().is_empty());
+  }
+
+  #[test]
+  fn garbage_pushes_the_stack_and_an_overlapping_piece_up() {
+    let mut engine = engine();
+    idle(&mut engine, Duration::ZERO);
+    fill(&mut engine, 0, 0..1);
+    let mut piece = engine.piece.clone().expect("a piece was dealt");
+    piece = piece.shifted(0, 1 - piece.blocks.iter().map(|block| block.1).min().unwrap());
+    engine.piece = Some(piece.clone());
+    let mut events = Vec::new();
+    engine.add_garbage(&[3, 5], &mut events);
+    assert_eq!(events, [EngineEvent::GarbageRose(2)]);
+    assert_eq!(engine.board.row(2), engine.board.full_row() & !1);
+    assert_eq!(engine.board.row(1), engine.board.full_row() & !(1 << 5));
+    assert_eq!(engine.board.row(0), engine.board.full_row() & !(1 << 3));
+    // the piece sat just above the stack, so it rises the same two rows.
+    assert_eq!(engine.piece, Some(piece.shifted(0, 2)));
+    assert!(!engine.is_over());
+  }
+
+  #[test]
+  fn undo_takes_back_the_stats_but_not_the_time() {
+    let settings = Settings {
+      seed: Some(7),
+      mode: GameMode::Zen,
+      are: Duration::ZERO,
+      ..default()
+    };
+    let mut engine = Engine::new(&settings, &BoardConfig::default());
+    idle(&mut engine, Duration::from_secs(1));
+    let before = engine.stats.clone();
+    let hard = Inputs {
+      hard_drop: true,
+      ..default()
+    };
+    engine.step(hard, Duration::from_secs(1));
+    idle(&mut engine, Duration::ZERO);
+    assert_eq!((engine.stats.pieces, engine.stats.keys), (1, 1));
+    let undo = Inputs {
+      undo: true,
+      ..default()
+    };
+    engine.step(undo, Duration::from_secs(1));
+    assert_eq!((engine.stats.pieces, engine.stats.keys), (before.pieces, before.keys));
+    assert_eq!(engine.stats.time, Duration::from_secs(3));
+    assert_eq!(engine.board.stack_height(), 0);
+  }
+
+  /// puts a T at `origin` facing `rotation` into a game whose matrix holds
+  /// only `filled`, turns it `quarter_turns` times clockwise, then hard drops
+  /// it, and returns how it scored.
+  fn spin_t(
+    filled: &[(i32, i32)],
+    rotation: Rotation,
+    origin: Tile,
+    quarter_turns: usize,
+  ) -> Option<ClearKind> {
+    let mut engine = engine();
+    idle(&mut engine, Duration::ZERO);
+    engine.board = Board::new(engine.board.width(), engine.board.height());
+    let mut filler = Tetromino::new(Shape::O, Rotation::Zero, Tile(0, 0), engine.rotation_system());
+    for &(x, y) in filled {
+      filler.blocks = [Tile(x, y); 4];
+      engine.board.lock(&filler);
+    }
+    engine.piece = Some(Tetromino::new(Shape::T, rotation, origin, engine.rotation_system()));
+    let turn = Inputs {
+      rotate_cw: quarter_turns == 1,
+      rotate_180: quarter_turns == 2,
+      rotate_ccw: quarter_turns == 3,
+      ..default()
+    };
+    assert!(engine.step(turn, Duration::ZERO).contains(&EngineEvent::Rotated));
+    let hard = Inputs {
+      hard_drop: true,
+      ..default()
+    };
+    engine.step(hard, Duration::ZERO).into_iter().find_map(|event| match event {
+      EngineEvent::Cleared(clear) => Some(clear.kind),
+      _ => None,
+    })
+  }
+
+  /// fills every cell of rows `0..rows` except those in `holes`.
+  fn rows(rows: i32, holes: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    (0..rows)
+      .flat_map(|y| (0..10).map(move |x| (x, y)))
+      .filter(|cell| !holes.contains(cell))
+      .collect()
+  }
+
+  #[test]
+  fn t_spin_with_both_front_corners_filled_is_full() {
+    // a T-spin double slot under an overhang at (3, 2).
+    let mut filled = rows(2, &[(4, 0), (3, 1), (4, 1), (5, 1)]);
+    filled.push((3, 2));
+    let kind = spin_t(&filled, Rotation::Right, Tile(3, 0), 1);
+    assert_eq!(kind, Some(ClearKind::TSpin(2)));
+  }
+
+  #[test]
+  fn t_spin_with_one_front_corner_filled_is_a_mini() {
+    let mut filled = rows(2, &[(4, 0), (5, 0), (3, 1), (4, 1), (5, 1)]);
+    filled.extend([(3, 2), (5, 2)]);
+    let kind = spin_t(&filled, Rotation::Right, Tile(3, 0), 1);
+    assert_eq!(kind, Some(ClearKind::TSpinMini(1)));
+  }
+
+  #[test]
+  fn last_kick_of_a_quarter_turn_makes_a_mini_full() {
+    // (4, 6) blocks every earlier kick and (4, 1) holds the T up. of the
+    // corners around its centre at (4, 3), (5, 2) in front is left open.
+    let filled = [(3, 4), (3, 2), (5, 4), (4, 6), (4, 1)];
+    let kind = spin_t(&filled, Rotation::Zero, Tile(4, 4), 1);
+    assert_eq!(kind, Some(ClearKind::TSpin(0)));
+  }
+
+  #[test]
+  fn half_turn_kicks_never_make_a_mini_full() {
+    // kicked by (1, 2), the third half turn kick from "R", onto (6, 5). of the
+    // corners around the T's centre at (6, 7), (5, 6) in front is left open.
+    let filled = [(7, 6), (7, 8), (5, 8), (4, 5), (6, 4), (6, 5)];
+    let kind = spin_t(&filled, Rotation::Right, Tile(4, 4), 2);
+    assert_eq!(kind, Some(ClearKind::TSpinMini(0)));
+  }
+
+  #[test]
+  fn t_which_moved_after_turning_is_not_a_t_spin() {
+    // three corners would be filled where the T lands, but it drops there
+    // after turning in the open.
+    let filled = [(3, 2), (3, 0), (5, 0)];
+    let kind = spin_t(&filled, Rotation::Zero, Tile(3, 5), 1);
+    assert_eq!(kind, None);
+  }
+
+  /// starts a game with an entry delay, locks its first piece, and deals a
+  /// T next.
+  fn t_next(engine: &mut Engine) {
+    idle(engine, Duration::ZERO);
+    let hard = Inputs {
+      hard_drop: true,
+      ..default()
+    };
+    engine.step(hard, Duration::ZERO);
+    engine.queue.0[0] = Tetromino::spawn(Shape::T, engine.rotation_system(), &engine.geometry);
+  }
+
+  #[test]
+  fn turn_pressed_during_the_entry_delay_applies_on_spawn() {
+    let settings = Settings {
+      seed: Some(7),
+      are: Duration::from_millis(100),
+      ..default()
+    };
+    let mut engine = Engine::new(&settings, &BoardConfig::default());
+    t_next(&mut engine);
+    let turn = Inputs {
+      rotate_cw: true,
+      ..default()
+    };
+    assert!(engine.step(turn, Duration::from_millis(50)).is_empty());
+    let events = idle(&mut engine, Duration::from_millis(50));
+    assert_eq!(events[..2], [EngineEvent::Spawned(Shape::T), EngineEvent::Rotated]);
+    let spawned = Tetromino::spawn(Shape::T, engine.rotation_system(), &engine.geometry);
+    let turned = spawned.rotated(Rotation::Right, engine.rotation_system());
+    assert_eq!(engine.piece.unwrap().blocks, turned.blocks);
+  }
+
+  #[test]
+  fn turn_into_the_stack_on_spawn_spawns_unturned() {
+    let settings = Settings {
+      seed: Some(7),
+      are: Duration::from_millis(100),
+      ..default()
+    };
+    let mut engine = Engine::new(&settings, &BoardConfig::default());
+    t_next(&mut engine);
+    let spawned = Tetromino::spawn(Shape::T, engine.rotation_system(), &engine.geometry);
+    let turned = spawned.rotated(Rotation::Right, engine.rotation_system());
+    let mut blocker = spawned.clone();
+    let in_the_way = turned.blocks.iter().find(|block| !spawned.blocks.contains(block));
+    blocker.blocks = [*in_the_way.unwrap(); 4];
+    engine.board.lock(&blocker);
+
+    let turn = Inputs {
+      rotate_cw: true,
+      ..default()
+    };
+    engine.step(turn, Duration::from_millis(50));
+    let events = idle(&mut engine, Duration::from_millis(50));
+    assert_eq!(events, [EngineEvent::Spawned(Shape::T)]);
+    assert_eq!(engine.piece.as_ref().unwrap().blocks, spawned.blocks);
+    assert!(!engine.is_over());
+  }
+
+  #[test]
+  fn hold_pressed_during_the_line_clear_delay_applies_on_spawn() {
+    let settings = Settings {
+      seed: Some(7),
+      line_clear_delay: Duration::from_millis(200),
+      are: Duration::ZERO,
+      ..default()
+    };
+    let mut engine = Engine::new(&settings, &BoardConfig::default());
+    idle(&mut engine, Duration::ZERO);
+    // a flat I spawns over columns 3 to 6.
+    fill(&mut engine, 0, 3..7);
+    engine.piece = Some(Tetromino::spawn(Shape::I, engine.rotation_system(), &engine.geometry));
+    let hard = Inputs {
+      hard_drop: true,
+      ..default()
+    };
+    assert!(engine.step(hard, Duration::ZERO).contains(&EngineEvent::LinesCleared(1)));
+    let next = engine.queue.0[0].shape;
+    let after = engine.queue.0[1].shape;
+
+    let hold = Inputs {
+      hold: true,
+      ..default()
+    };
+    assert!(engine.step(hold, Duration::from_millis(100)).is_empty());
+    let events = idle(&mut engine, Duration::from_millis(100));
+    let spawns = [
+      EngineEvent::Spawned(next),
+      EngineEvent::Held(next),
+      EngineEvent::Spawned(after),
+    ];
+    assert!(events.windows(3).any(|window| window == spawns), "{events:?}");
+    assert_eq!(engine.data.held_piece, Some(next));
+    assert_eq!(engine.piece.unwrap().shape, after);
+  }
+
+  /// starts a big game of `mode` on the standard board.
+  fn big(mode: GameMode) -> Engine {
+    let settings = Settings {
+      seed: Some(7),
+      mode,
+      big: true,
+      are: Duration::ZERO,
+      line_clear_delay: Duration::ZERO,
+      ..default()
+    };
+    Engine::new(&settings, &BoardConfig::STANDARD)
+  }
+
+  #[test]
+  fn big_cells_cover_two_by_two_standard_cells() {
+    let mut engine = big(GameMode::Endless);
+    assert_eq!((engine.geometry.cols, engine.geometry.rows), (5, 10));
+    assert_eq!(engine.board.width(), 5);
+    assert_eq!(engine.geometry.size(), Geometry::default().size());
+    idle(&mut engine, Duration::ZERO);
+    let piece = engine.piece.clone().unwrap();
+    // every block of the piece is drawn two standard cells from its neighbours.
+    for a in piece.blocks {
+      for b in piece.blocks.iter().filter(|b| (a.0 - b.0).abs() + (a.1 - b.1).abs() == 1) {
+        let apart = engine.geometry.translation(a).distance(engine.geometry.translation(*b));
+        assert_eq!(apart, 2.0 * Tile::LENGTH);
+      }
+    }
+    assert_eq!(engine.geometry.cell_length(), 2.0 * Tile::LENGTH);
+  }
+
+  #[test]
+  fn big_row_clears_as_a_single_counting_two_lines() {
+    let mut engine = big(GameMode::Endless);
+    idle(&mut engine, Duration::ZERO);
+    let piece = Tetromino::spawn(Shape::I, engine.rotation_system(), &engine.geometry);
+    let left = piece.blocks.iter().map(|block| block.0).min().unwrap();
+    fill(&mut engine, 0, left..left + 4);
+    engine.piece = Some(piece);
+    let hard = Inputs {
+      hard_drop: true,
+      ..default()
+    };
+    let events = engine.step(hard, Duration::ZERO);
+    assert!(events.contains(&EngineEvent::LinesCleared(1)));
+    let clear = events.iter().find_map(|event| match event {
+      EngineEvent::Cleared(clear) => Some(clear.kind),
+      _ => None,
+    });
+    assert_eq!(clear, Some(ClearKind::Lines(1)));
+    assert_eq!(engine.score.lines, 2);
+  }
+
+  #[test]
+  fn big_needs_a_board_which_halves_evenly() {
+    let narrow = BoardConfig {
+      width: 4,
+      ..BoardConfig::STANDARD
+    };
+    assert!(!narrow.fits_big());
+    assert!(BoardConfig::STANDARD.fits_big());
+    let settings = Settings {
+      big: true,
+      ..default()
+    };
+    let engine = Engine::new(&settings, &narrow);
+    assert_eq!(engine.geometry.scale, 1);
+    assert_eq!(engine.board.width(), 4);
+  }
+
+  #[test]
+  fn big_garbage_rows_count_twice_towards_dig() {
+    let mut engine = big(GameMode::Dig {
+      lines: 10,
+      rise: None,
+    });
+    idle(&mut engine, Duration::ZERO);
+    // ten lines of garbage take five big rows.
+    assert_eq!(engine.board.garbage_rows(), 5);
+    let hole = (0..5).find(|x| engine.board.get(*x, 4).is_none()).unwrap();
+    let mut filler = engine.piece.clone().unwrap();
+    filler.blocks = [Tile(hole, 4); 4];
+    engine.board.lock(&filler);
+    let hard = Inputs {
+      hard_drop: true,
+      ..default()
+    };
+    let events = engine.step(hard, Duration::ZERO);
+    assert!(events.contains(&EngineEvent::LinesCleared(1)));
+    assert_eq!(engine.stats.garbage_cleared, 2);
+    assert_eq!(engine.board.garbage_rows(), 4);
+  }
+
+  /// starts an endless game on the given board.
+  fn on_board(config: BoardConfig) -> Engine {
+    let settings = Settings {
+      seed: Some(7),
+      are: Duration::ZERO,
+      line_clear_delay: Duration::ZERO,
+      ..default()
+    };
+    Engine::new(&settings, &config)
+  }
+
+  #[test]
+  fn pieces_spawn_centred_and_clear_lines_on_narrow_and_wide_boards() {
+    for width in [4, 12] {
+      let mut engine = on_board(BoardConfig {
+        width,
+        ..BoardConfig::STANDARD
+      });
+      assert_eq!(engine.board.width(), width);
+      idle(&mut engine, Duration::ZERO);
+      let piece = Tetromino::spawn(Shape::I, engine.rotation_system(), &engine.geometry);
+      let left = piece.blocks.iter().map(|block| block.0).min().unwrap();
+      let right = piece.blocks.iter().map(|block| block.0).max().unwrap();
+      assert_eq!(left + right + 1, width as i32, "{width} wide");
+      fill(&mut engine, 0, left..right + 1);
+      engine.piece = Some(piece);
+      let hard = Inputs {
+        hard_drop: true,
+        ..default()
+      };
+      let events = engine.step(hard, Duration::ZERO);
+      assert!(events.contains(&EngineEvent::LinesCleared(1)), "{width} wide");
+      assert_eq!(engine.score.lines, 1);
+      assert!((0..width as i32).all(|x| engine.board.get(x, 0).is_none()));
+    }
+  }
+
+  #[test]
+  fn tall_board_keeps_its_buffer_above_the_visible_field() {
+    let config = BoardConfig {
+      height: 40,
+      ..BoardConfig::STANDARD
+    };
+    let mut engine = on_board(config);
+    assert_eq!(engine.geometry.rows, 40);
+    assert_eq!(engine.board.height(), 40 + config.buffer);
+    // pieces enter in the buffer, above row 40 rather than row 20.
+    let piece = Tetromino::spawn(Shape::I, engine.rotation_system(), &engine.geometry);
+    let buffer = 40..engine.board.height() as i32;
+    assert!(piece.blocks.iter().all(|block| buffer.contains(&block.1)));
+    idle(&mut engine, Duration::ZERO);
+    let piece = engine.piece.clone().unwrap();
+    assert!(piece.blocks.iter().all(|block| block.1 >= 38));
+  }
+
+  #[test]
+  fn spawning_into_the_stack_is_a_block_out() {
+    let mut engine = engine();
+    let visible = engine.geometry.rows as i32;
+    for y in visible - 2..visible + 2 {
+      fill(&mut engine, y, 0..0);
+    }
+    let events = idle(&mut engine, Duration::ZERO);
+    let game_over = EngineEvent::GameOver(Outcome::ToppedOut(TopOut::BlockOut));
+    assert_eq!(events.last(), Some(&game_over));
+    assert!(engine.is_over());
+    assert!(idle(&mut engine, Duration::from_secs(1)).is_empty());
+  }
+
+  /// stacks the matrix `height` rows high, leaving the left column open so
+  /// nothing clears, then hard drops `shape` onto it, turned to `rotation`
+  /// over columns 4 and up, and returns what happened.
+  fn drop_onto_stack(
+    engine: &mut Engine,
+    height: i32,
+    shape: Shape,
+    rotation: Rotation,
+  ) -> Vec<EngineEvent> {
+    idle(engine, Duration::ZERO);
+    for y in 0..height {
+      fill(engine, y, 0..1);
+    }
+    let piece = Tetromino::new(shape, rotation, Tile(0, 0), engine.rotation_system());
+    let column = piece.blocks.iter().map(|block| block.0).min().unwrap();
+    let top = engine.board.height() as i32 - 4;
+    engine.place(piece.shifted(4 - column, top - Engine::bottom(&piece)));
+    let hard = Inputs {
+      hard_drop: true,
+      ..default()
+    };
+    engine.step(hard, Duration::ZERO)
+  }
+
+  #[test]
+  fn locking_wholly_above_the_field_is_a_lock_out() {
+    let mut engine = engine();
+    let visible = engine.geometry.rows as i32;
+    let events = drop_onto_stack(&mut engine, visible, Shape::O, Rotation::Zero);
+    let game_over = EngineEvent::GameOver(Outcome::ToppedOut(TopOut::LockOut));
+    assert_eq!(events.last(), Some(&game_over));
+    assert!(engine.is_over());
+  }
+
+  #[test]
+  fn locking_partly_above_the_field_is_a_partial_lock_out_if_enabled() {
+    let mut engine = engine();
+    engine.settings.partial_lock_out = true;
+    let visible = engine.geometry.rows as i32;
+    // an upright I over the top row of the field and three above it.
+    let events = drop_onto_stack(&mut engine, visible - 1, Shape::I, Rotation::Right);
+    let game_over = EngineEvent::GameOver(Outcome::ToppedOut(TopOut::PartialLockOut));
+    assert_eq!(events.last(), Some(&game_over));
+    assert!(engine.is_over());
+  }
+
+  #[test]
+  fn locking_partly_above_the_field_is_allowed_by_default() {
+    let mut engine = engine();
+    assert!(!engine.settings.partial_lock_out);
+    let visible = engine.geometry.rows as i32;
+    let events = drop_onto_stack(&mut engine, visible - 1, Shape::I, Rotation::Right);
+    assert!(events.contains(&EngineEvent::Locked(Shape::I)));
+    assert!(!events.iter().any(|event| matches!(event, EngineEvent::GameOver(_))));
+    assert!(!engine.is_over());
+    assert_eq!(engine.board.stack_height(), visible as usize + 3);
+  }
+}
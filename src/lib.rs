@@ -8,10 +8,15 @@
 pub mod assets;
 pub mod board;
 pub mod dev_tools;
+pub mod engine;
 pub mod keymap;
 pub mod menu;
+pub mod mode;
 pub mod palette;
+pub mod randomizer;
+pub mod rotation;
 pub mod score;
+pub mod settings;
 pub mod state;
 pub mod tetromino;
 pub mod timers;
@@ -32,14 +37,7 @@ impl Plugin for AppPlugin {
     Self::register_types(app);
     Self::register_states(app);
     // core application logic.
-    app
-      .init_resource::<timers::GameTimers>()
-      .insert_resource({
-        let mut queue = VecDeque::new();
-        queue.extend(Tetromino::random_n(7));
-        Queue(queue)
-      })
-      .add_plugin(GameBoard);
+    app.add_plugin(GameBoard);
     // global keymaps & app management.
     Self::add_systems(app);
   }
@@ -99,13 +97,19 @@ impl AppPlugin {
       .add_event::<GameEvent>()
       .register_type::<GameTimers>()
       .register_type::<GameAssets>()
+      .register_type::<Settings>()
+      .register_type::<BoardConfig>()
       .register_type::<GameState>()
+      .register_type::<PlayPhase>()
       .register_type::<AppState>();
   }
 
   /// register all states used by this plugin.
   fn register_states(app: &mut App) {
-    app.add_state::<AppState>().add_state::<GameState>();
+    app
+      .add_state::<AppState>()
+      .add_state::<GameState>()
+      .add_state::<PlayPhase>();
   }
 
   /// setup egui with our custom theme.
@@ -122,7 +126,13 @@ impl AppPlugin {
   /// add all systems used by this plugin.
   fn add_systems(app: &mut App) {
     app
-    .add_startup_systems((Scoreboard::setup, Self::setup_egui, Self::spawn_camera))
+    .add_startup_systems((
+      Scoreboard::setup,
+      LevelMeter::setup,
+      crate::menu::GravityToggle::setup,
+      Self::setup_egui,
+      Self::spawn_camera,
+    ))
     // game logic
     .add_systems((
       keymap::handle_quit,
@@ -134,7 +144,7 @@ impl AppPlugin {
               .or_else(state_exists_and_equals(GameState::Paused)),
       ),
       // Game Restarted
-      Score::reset.in_schedule(OnEnter(GameState::Restarting)),
+      GameBoard::reset.in_schedule(OnEnter(GameState::Restarting)),
       crate::state::play_game.in_set(OnUpdate(GameState::Restarting)),
       // menu setup systems:
       crate::menu::setup_game_paused_menu.in_schedule(OnEnter(GameState::Paused)),
@@ -142,6 +152,10 @@ impl AppPlugin {
       // menu cleanup systems:
       despawn_screen::<crate::menu::OnGamePausedMenuScreen>.in_schedule(OnExit(GameState::Paused)),
       despawn_screen::<crate::menu::OnMainMenuScreen>.in_schedule(OnExit(AppState::MainMenu)),
+      // in-game widgets, left over the menus once the game ends or is quit:
+      hide_screen::<LevelMeter>.in_schedule(OnEnter(GameState::Quitting)),
+      hide_screen::<crate::menu::GravityToggle>.in_schedule(OnEnter(GameState::Quitting)),
+      crate::menu::update_setting_labels.run_if(state_exists_and_equals(AppState::MainMenu)),
       // menu button system.
       crate::menu::click_button.run_if(
           state_exists_and_equals(AppState::MainMenu)
@@ -152,33 +166,42 @@ impl AppPlugin {
     // Game Playing
     .add_systems(
         (
-            Movable::update_pieces,
-            board::remove_piece_component,
-            board::detect_game_over.after(board::remove_piece_component),
-            board::check_full_line.after(board::remove_piece_component),
+            GameBoard::step,
+            GameBoard::sync_piece,
+            GameBoard::sync_stack,
+            GameBoard::sync_phase,
+            GameBoard::update_tile_visibility,
+            GhostTetromino::update,
+            NextTetromino::update,
+            HeldTetromino::update,
+            board::play_sounds,
+            board::detect_game_over,
+            Scoreboard::update,
+            LevelMeter::update,
+            crate::menu::GravityToggle::update,
         )
-            .in_base_set(CoreSet::PostUpdate)
-            .distributive_run_if(GameState::is_playing_state),
+            .chain()
+            .in_set(OnUpdate(GameState::Playing)),
     )
     .add_systems(
         (
-            NextTetromino::update,
-            tetromino::spawn_next_piece,
-            GameBoard::update_tile_visibility,
-            keymap::rotate_piece,
-            keymap::move_piece,
-            keymap::hold_piece,
-            Scoreboard::update,
+            GameBoard::fade_tiles.after(GameBoard::sync_stack),
+            GameBoard::animate_line_clear
+                .in_set(OnUpdate(PlayPhase::LineClear))
+                .after(GameBoard::fade_tiles),
+            GameBoard::settle_tiles.after(GameBoard::sync_stack),
         )
-            .in_set(OnUpdate(GameState::Playing)),
+            .distributive_run_if(state_exists_and_equals(GameState::Playing)),
     )
     // Main Menu
     .add_systems(
         (
             crate::menu::setup_main_menu,
             Tile::clear_all,
-            Score::reset,
+            GameBoard::reset,
             NextTetromino::clear,
+            HeldTetromino::clear,
+            GhostTetromino::clear,
         )
             .in_schedule(OnEnter(AppState::MainMenu)),
     )
@@ -187,8 +210,10 @@ impl AppPlugin {
         (
             despawn_screen::<crate::menu::OnGameOverMenuScreen>,
             Tile::clear_all,
-            Score::reset,
+            GameBoard::reset,
             NextTetromino::clear,
+            HeldTetromino::clear,
+            GhostTetromino::clear,
         )
             .in_schedule(OnExit(AppState::GameOver)),
     );
@@ -1,5 +1,5 @@
 //! menu system(s).
-use crate::prelude::*;
+use crate::{prelude::*, score::format_time};
 
 #[derive(Component)]
 pub struct OnMainMenuScreen;
@@ -23,9 +23,131 @@ pub enum MenuButtonAction {
   ResumeGame,
   /// Quit the game.
   Quit,
+  /// Switch to the next game mode.
+  CycleMode,
+  /// Switch to the next piece randomizer.
+  CycleRandomizer,
+  /// Switch to the next rotation system.
+  CycleRotationSystem,
+  /// Show one more upcoming piece, wrapping back to one.
+  CyclePreview,
+  /// Show or hide the ghost piece.
+  ToggleGhost,
+  /// Switch to the next way of showing the stack.
+  CycleStack,
+  /// Play with big pieces, or not.
+  ToggleBig,
+  /// Switch to the next board size.
+  CycleBoard,
+  /// Start one level higher, wrapping back to the first.
+  CycleStartLevel,
 }
 
-pub fn setup_main_menu(mut commands: Commands, game_assets: Res<GameAssets>) {
+/// marks menu text which displays the current value of a [`Settings`] field,
+/// or of the [`BoardConfig`].
+#[derive(Debug, Clone, Copy, Component)]
+pub enum SettingLabel {
+  /// the game mode.
+  Mode,
+  /// the piece randomizer.
+  Randomizer,
+  /// the rotation system.
+  RotationSystem,
+  /// how many upcoming pieces are shown.
+  Preview,
+  /// whether the ghost piece is shown.
+  Ghost,
+  /// how much of the stack is shown.
+  Stack,
+  /// whether the game is played in big mode.
+  Big,
+  /// the size of the board.
+  Board,
+  /// the level games start at.
+  StartLevel,
+}
+
+impl SettingLabel {
+  /// returns the text to display for the current `settings` and board `config`.
+  #[must_use]
+  pub fn text(&self, settings: &Settings, config: &BoardConfig) -> String {
+    match self {
+      Self::Mode => format!("Mode: {}", settings.mode),
+      Self::Randomizer => format!("Randomizer: {}", settings.randomizer),
+      Self::RotationSystem => format!("Rotation: {}", settings.rotation_system),
+      Self::Preview => format!("Preview: {}", settings.preview_count),
+      Self::Ghost => format!("Ghost: {}", if settings.ghost { "On" } else { "Off" }),
+      Self::Stack => format!("Stack: {}", settings.stack),
+      // big mode is kept for boards which fit it, but shown as it will be played.
+      Self::Big => match (settings.big, config.fits_big()) {
+        (true, true) => "Big: On".to_string(),
+        (true, false) => "Big: Off (not on this board)".to_string(),
+        (false, _) => "Big: Off".to_string(),
+      },
+      Self::Board => format!("Board: {config}"),
+      Self::StartLevel => format!("Start Level: {}", settings.start_level),
+    }
+  }
+}
+
+/// the setting buttons of the main menu, top to bottom, with the value each one shows.
+const SETTING_BUTTONS: [(MenuButtonAction, SettingLabel); 9] = [
+  (MenuButtonAction::CycleMode, SettingLabel::Mode),
+  (MenuButtonAction::CycleRandomizer, SettingLabel::Randomizer),
+  (MenuButtonAction::CycleRotationSystem, SettingLabel::RotationSystem),
+  (MenuButtonAction::CyclePreview, SettingLabel::Preview),
+  (MenuButtonAction::ToggleGhost, SettingLabel::Ghost),
+  (MenuButtonAction::CycleStack, SettingLabel::Stack),
+  (MenuButtonAction::ToggleBig, SettingLabel::Big),
+  (MenuButtonAction::CycleBoard, SettingLabel::Board),
+  (MenuButtonAction::CycleStartLevel, SettingLabel::StartLevel),
+];
+
+/// spawns a button which changes a setting when clicked, labelled with its current value.
+fn spawn_setting_button(
+  parent: &mut ChildBuilder,
+  game_assets: &GameAssets,
+  settings: &Settings,
+  config: &BoardConfig,
+  action: MenuButtonAction,
+  label: SettingLabel,
+) {
+  parent
+    .spawn((
+      ButtonBundle {
+        style: Style {
+          size: Size::new(Val::Percent(65.0), Val::Px(35.0)),
+          margin: UiRect::vertical(Val::Px(5.0)),
+          justify_content: JustifyContent::Center,
+          align_items: AlignItems::Center,
+          ..default()
+        },
+        background_color: palette::SURFACE0.into(),
+        ..default()
+      },
+      action,
+    ))
+    .with_children(|parent| {
+      parent.spawn((
+        TextBundle::from_section(
+          label.text(settings, config),
+          TextStyle {
+            font: game_assets.bold_font(),
+            font_size: 18.0,
+            color: palette::TEXT,
+          },
+        ),
+        label,
+      ));
+    });
+}
+
+pub fn setup_main_menu(
+  mut commands: Commands,
+  game_assets: Res<GameAssets>,
+  settings: Res<Settings>,
+  config: Res<BoardConfig>,
+) {
   commands
     .spawn((
       NodeBundle {
@@ -45,8 +167,9 @@ pub fn setup_main_menu(mut commands: Commands, game_assets: Res<GameAssets>) {
       parent
         .spawn(NodeBundle {
           style: Style {
-            size: Size::new(Val::Percent(33.0), Val::Percent(50.0)),
+            size: Size::new(Val::Percent(33.0), Val::Auto),
             margin: UiRect::vertical(Val::Px(25.0)),
+            padding: UiRect::bottom(Val::Px(10.0)),
             flex_direction: FlexDirection::Column,
             align_items: AlignItems::Center,
             ..default()
@@ -82,7 +205,7 @@ pub fn setup_main_menu(mut commands: Commands, game_assets: Res<GameAssets>) {
             .spawn((
               ButtonBundle {
                 style: Style {
-                  size: Size::new(Val::Percent(65.0), Val::Percent(65.0)),
+                  size: Size::new(Val::Percent(65.0), Val::Px(60.0)),
                   margin: UiRect::vertical(Val::Px(10.0)),
                   justify_content: JustifyContent::Center,
                   align_items: AlignItems::Center,
@@ -103,11 +226,14 @@ pub fn setup_main_menu(mut commands: Commands, game_assets: Res<GameAssets>) {
                 },
               ));
             });
+          for (action, label) in SETTING_BUTTONS {
+            spawn_setting_button(parent, &game_assets, &settings, &config, action, label);
+          }
           parent
             .spawn((
               ButtonBundle {
                 style: Style {
-                  size: Size::new(Val::Percent(35.0), Val::Percent(50.0)),
+                  size: Size::new(Val::Percent(35.0), Val::Px(40.0)),
                   margin: UiRect::vertical(Val::Px(15.0)),
                   justify_content: JustifyContent::Center,
                   align_items: AlignItems::Center,
@@ -132,7 +258,26 @@ pub fn setup_main_menu(mut commands: Commands, game_assets: Res<GameAssets>) {
     });
 }
 
-pub fn setup_game_over_menu(mut commands: Commands, game_assets: Res<GameAssets>) {
+/// sets up the results screen, shown once a game is over.
+pub fn setup_game_over_menu(
+  mut commands: Commands,
+  game_assets: Res<GameAssets>,
+  engine: Res<GameEngine>,
+) {
+  let title = engine
+    .outcome
+    .map_or_else(|| "Game Over".to_string(), |outcome| outcome.to_string());
+  let stats = &engine.stats;
+  let mut results = vec![
+    format!("Mode: {}", engine.settings.mode),
+    format!("Time: {}", format_time(stats.time)),
+    format!("Pieces: {}", stats.pieces),
+    format!("PPS: {:.2}", stats.pps()),
+    format!("KPP: {:.2}", stats.kpp()),
+    format!("Lines: {}", engine.score.lines),
+    format!("Score: {}", engine.score.points),
+  ];
+  results.extend(engine.mode().summary(&engine.score, stats));
   commands
     .spawn((
       NodeBundle {
@@ -160,7 +305,7 @@ pub fn setup_game_over_menu(mut commands: Commands, game_assets: Res<GameAssets>
         .with_children(|parent| {
           parent.spawn(
             TextBundle::from_section(
-              "Game Over",
+              title,
               TextStyle {
                 font: game_assets.bold_font(),
                 font_size: 25.0,
@@ -172,6 +317,22 @@ pub fn setup_game_over_menu(mut commands: Commands, game_assets: Res<GameAssets>
               ..default()
             }),
           );
+          for result in results {
+            parent.spawn(
+              TextBundle::from_section(
+                result,
+                TextStyle {
+                  font: game_assets.bold_font(),
+                  font_size: 20.0,
+                  color: palette::SUBTEXT1,
+                },
+              )
+              .with_style(Style {
+                margin: UiRect::horizontal(Val::Px(20.0)),
+                ..default()
+              }),
+            );
+          }
 
           parent
             .spawn((
@@ -309,6 +470,8 @@ pub fn click_button(
   >,
   mut app_state: ResMut<NextState<AppState>>,
   mut game_state: ResMut<NextState<GameState>>,
+  mut settings: ResMut<Settings>,
+  mut config: ResMut<BoardConfig>,
   mut exit: EventWriter<AppExit>,
 ) {
   for (interaction, menu_button_action) in &mut interaction_query {
@@ -317,8 +480,9 @@ pub fn click_button(
         info!("{:?} button clicked", menu_button_action);
         match menu_button_action {
           MenuButtonAction::StartGame => {
+            // restart, so the game is built from the settings just chosen.
             app_state.set(AppState::InGame);
-            game_state.set(GameState::Playing);
+            game_state.set(GameState::Restarting);
           }
           MenuButtonAction::RestartGame => {
             app_state.set(AppState::InGame);
@@ -335,9 +499,117 @@ pub fn click_button(
           MenuButtonAction::Quit => {
             exit.send_default();
           }
+          MenuButtonAction::CycleMode => {
+            settings.mode = settings.mode.cycle();
+          }
+          MenuButtonAction::CycleRandomizer => {
+            settings.randomizer = settings.randomizer.cycle();
+          }
+          MenuButtonAction::CycleRotationSystem => {
+            settings.rotation_system = settings.rotation_system.cycle();
+          }
+          MenuButtonAction::CyclePreview => {
+            settings.preview_count = settings.cycle_preview();
+          }
+          MenuButtonAction::ToggleGhost => {
+            settings.ghost = !settings.ghost;
+          }
+          MenuButtonAction::CycleStack => {
+            settings.stack = settings.stack.cycle();
+          }
+          MenuButtonAction::ToggleBig => {
+            settings.big = !settings.big;
+          }
+          MenuButtonAction::CycleBoard => {
+            *config = config.cycle();
+          }
+          MenuButtonAction::CycleStartLevel => {
+            settings.start_level = settings.cycle_start_level();
+          }
         }
       }
       _ => {}
     }
   }
 }
+
+/// [system][`System`] which keeps every [`SettingLabel`] showing the current
+/// [`Settings`] and [`BoardConfig`].
+pub fn update_setting_labels(
+  settings: Res<Settings>,
+  config: Res<BoardConfig>,
+  mut query: Query<(&mut Text, &SettingLabel)>,
+) {
+  if !settings.is_changed() && !config.is_changed() {
+    return;
+  }
+  for (mut text, label) in &mut query {
+    text.sections[0].value = label.text(&settings, &config);
+  }
+}
+
+/// marks the on-screen button which turns gravity on or off, in modes which allow it.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct GravityToggle;
+
+impl GravityToggle {
+  /// returns the label for the button, given whether gravity is on.
+  fn text(gravity: bool) -> String {
+    format!("Gravity: {}", if gravity { "On" } else { "Off" })
+  }
+
+  /// spawns the (hidden) button.
+  pub(super) fn setup(mut commands: Commands, game_assets: Res<GameAssets>) {
+    commands
+      .spawn((
+        ButtonBundle {
+          style: Style {
+            size: Size::new(Val::Px(160.0), Val::Px(30.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            position_type: PositionType::Absolute,
+            position: UiRect {
+              left: Val::Percent(5.0),
+              bottom: Val::Percent(4.0),
+              ..default()
+            },
+            ..default()
+          },
+          background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+          visibility: Visibility::Hidden,
+          ..default()
+        },
+        GravityToggle,
+        Name::new("Gravity Toggle"),
+      ))
+      .with_children(|parent| {
+        parent.spawn(game_assets.button_text_bundle(&Self::text(true)));
+      });
+  }
+
+  /// [system][`System`] which shows the button when the [`Engine`]'s mode
+  /// allows turning gravity off, and toggles it when clicked.
+  pub(super) fn update(
+    mut engine: ResMut<GameEngine>,
+    clicks: Query<&Interaction, (Changed<Interaction>, With<Self>)>,
+    mut buttons: Query<(&mut Visibility, &Children), With<Self>>,
+    mut texts: Query<&mut Text>,
+  ) {
+    if clicks.iter().any(|interaction| *interaction == Interaction::Clicked) {
+      engine.toggle_gravity();
+    }
+    let shown = engine.mode().can_toggle_gravity();
+    for (mut visibility, children) in &mut buttons {
+      *visibility = if shown {
+        Visibility::Inherited
+      } else {
+        Visibility::Hidden
+      };
+      for child in children {
+        if let Ok(mut text) = texts.get_mut(*child) {
+          text.sections[0].value = Self::text(engine.gravity);
+        }
+      }
+    }
+  }
+}
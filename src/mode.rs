@@ -0,0 +1,745 @@
+//! game modes, which decide when a game is won or lost.
+use crate::{engine::TopOut, prelude::*, score::Stats};
+
+/// the rules of a game mode: when it is won or lost, and what the player is
+/// racing against.
+///
+/// topping out ends the game unless the mode forgives it (see
+/// [`Mode::forgives_top_out`]); a mode adds its own ways for the game to end.
+pub trait Mode: fmt::Debug + Send + Sync {
+  /// returns how the game ended, if the mode's goal (or limit) was reached.
+  fn outcome(&self, score: &Score, stats: &Stats) -> Option<Outcome>;
+
+  /// how many lines the player has to clear, if the mode has a line goal.
+  fn line_goal(&self) -> Option<u32> {
+    None
+  }
+
+  /// how many lines it takes to go up a level, if the mode levels up at all.
+  /// by default, every [`Score::LINES_PER_LEVEL`] lines.
+  fn level_goal(&self) -> Option<u32> {
+    Some(Score::LINES_PER_LEVEL)
+  }
+
+  /// how many garbage rows the matrix should be topped up to, now that the
+  /// player has played so far. like lines, a row counts twice in big mode.
+  fn garbage_rows(&self, _stats: &Stats) -> usize {
+    0
+  }
+
+  /// how often a garbage row rises from the bottom, if they rise at all.
+  fn garbage_interval(&self) -> Option<Duration> {
+    None
+  }
+
+  /// how many garbage rows the player has to clear, if the mode has a garbage goal.
+  fn garbage_goal(&self) -> Option<u32> {
+    None
+  }
+
+  /// does topping out empty the matrix, rather than end the game?
+  fn forgives_top_out(&self) -> bool {
+    false
+  }
+
+  /// how many of the last placements the player may take back.
+  fn undo_depth(&self) -> usize {
+    0
+  }
+
+  /// may the player turn gravity off?
+  fn can_toggle_gravity(&self) -> bool {
+    false
+  }
+
+  /// how long the player has left, if the mode is played against the clock.
+  fn time_left(&self, _stats: &Stats) -> Option<Duration> {
+    None
+  }
+
+  /// how fast the game is played, now that the player has scored `score`.
+  /// by default, gravity follows the level and the delays follow the settings.
+  fn timing(&self, score: &Score, settings: &Settings) -> Timing {
+    Timing {
+      gravity: score.level.gravity(),
+      are: settings.are,
+      lock_delay: settings.lock_delay,
+      line_clear_delay: settings.line_clear_delay,
+    }
+  }
+
+  /// a piece was dealt from the queue.
+  fn on_spawn(&mut self, _stats: &Stats) {}
+
+  /// a piece locked, clearing `cleared` lines, and emptying the matrix if
+  /// `perfect_clear`.
+  fn on_lock(&mut self, _cleared: usize, _perfect_clear: bool, _stats: &Stats) {}
+
+  /// what the mode has to say about the player's progress, if the scoreboard
+  /// should show it in place of the level.
+  fn progress(&self) -> Option<String> {
+    None
+  }
+
+  /// extra lines for the results screen.
+  fn summary(&self, _score: &Score, _stats: &Stats) -> Vec<String> {
+    Vec::new()
+  }
+}
+
+/// how fast a game is played: gravity, and the delays around each piece.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timing {
+  /// how fast pieces fall, in cells per frame.
+  pub gravity: f32,
+  /// the entry delay, between one piece locking and the next spawning.
+  pub are: Duration,
+  /// how long a piece may rest on the stack before it locks.
+  pub lock_delay: Duration,
+  /// how long cleared lines take to disappear.
+  pub line_clear_delay: Duration,
+}
+
+impl Timing {
+  /// returns how long it takes a piece to fall one row.
+  #[must_use]
+  pub fn fall_interval(&self) -> Duration {
+    Duration::from_secs_f32(1.0 / (self.gravity * Level::FRAMES_PER_SECOND))
+  }
+
+  /// do pieces drop to the floor as soon as they spawn or move (20G)?
+  #[must_use]
+  pub fn is_instant(&self) -> bool {
+    self.gravity >= Level::MAX_GRAVITY
+  }
+}
+
+/// play until topping out.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Endless;
+
+impl Mode for Endless {
+  fn outcome(&self, _score: &Score, _stats: &Stats) -> Option<Outcome> {
+    None
+  }
+}
+
+/// clear a number of lines as fast as possible.
+#[derive(Debug, Clone, Copy)]
+pub struct Sprint {
+  /// how many lines finish the race.
+  pub lines: u32,
+}
+
+impl Mode for Sprint {
+  fn outcome(&self, score: &Score, _stats: &Stats) -> Option<Outcome> {
+    (score.lines >= self.lines).then_some(Outcome::Completed)
+  }
+
+  fn line_goal(&self) -> Option<u32> {
+    Some(self.lines)
+  }
+
+  /// a race is played at one speed throughout.
+  fn level_goal(&self) -> Option<u32> {
+    None
+  }
+}
+
+/// level up every [`Score::LINES_PER_LEVEL`] lines, until a line goal (if any).
+#[derive(Debug, Clone, Copy)]
+pub struct Marathon {
+  /// how many lines finish the marathon, or [`None`] to play until topping out.
+  pub lines: Option<u32>,
+}
+
+impl Mode for Marathon {
+  fn outcome(&self, score: &Score, _stats: &Stats) -> Option<Outcome> {
+    self
+      .lines
+      .is_some_and(|lines| score.lines >= lines)
+      .then_some(Outcome::Completed)
+  }
+
+  fn line_goal(&self) -> Option<u32> {
+    self.lines
+  }
+}
+
+/// dig through a number of garbage rows as fast as possible.
+#[derive(Debug, Clone, Copy)]
+pub struct Dig {
+  /// how many garbage rows finish the race.
+  pub lines: u32,
+  /// how often another garbage row rises, if they rise at all.
+  pub rise: Option<Duration>,
+}
+
+impl Dig {
+  /// the most garbage rows the matrix is topped up to.
+  pub const MAX_ROWS: u32 = 10;
+}
+
+impl Mode for Dig {
+  fn outcome(&self, _score: &Score, stats: &Stats) -> Option<Outcome> {
+    (stats.garbage_cleared >= self.lines).then_some(Outcome::Completed)
+  }
+
+  fn garbage_rows(&self, stats: &Stats) -> usize {
+    let left = self.lines.saturating_sub(stats.garbage_cleared);
+    left.min(Self::MAX_ROWS) as usize
+  }
+
+  fn garbage_interval(&self) -> Option<Duration> {
+    self.rise
+  }
+
+  fn garbage_goal(&self) -> Option<u32> {
+    Some(self.lines)
+  }
+}
+
+/// practice without pressure: topping out only empties the matrix, the last
+/// placements may be taken back, and gravity may be turned off.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Zen;
+
+impl Zen {
+  /// how many of the last placements may be taken back.
+  pub const UNDO_DEPTH: usize = 100;
+}
+
+impl Mode for Zen {
+  fn outcome(&self, _score: &Score, _stats: &Stats) -> Option<Outcome> {
+    None
+  }
+
+  fn forgives_top_out(&self) -> bool {
+    true
+  }
+
+  fn undo_depth(&self) -> usize {
+    Self::UNDO_DEPTH
+  }
+
+  fn can_toggle_gravity(&self) -> bool {
+    true
+  }
+}
+
+/// climb from level 0 to 999 as gravity ramps up to 20G and the delays
+/// shrink, racing each section of 100 levels against its target time.
+///
+/// the level goes up with every piece dealt and every line cleared, but only
+/// a line clear may finish a section (at 99, 199, ... and 998).
+///
+/// the grade is earned with points scored as in TGM, which the mode keeps
+/// itself, rather than with the game's [`Score`].
+#[derive(Debug, Default, Clone)]
+pub struct Master {
+  /// the level, from 0 to [`Master::MAX_LEVEL`].
+  pub level: u32,
+  /// when each section was finished.
+  pub sections: Vec<Duration>,
+  /// points scored towards the grade.
+  pub points: u32,
+  /// how far the combo multiplier has grown past 1, over clears in a row.
+  pub combo: u32,
+}
+
+/// a grade awarded at the end of a game of [`Master`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Grade(usize);
+
+impl Grade {
+  /// every grade, lowest first.
+  pub const NAMES: [&'static str; 19] = [
+    "9", "8", "7", "6", "5", "4", "3", "2", "1", "S1", "S2", "S3", "S4", "S5", "S6", "S7", "S8", "S9",
+    "GM",
+  ];
+  /// the grand master grade.
+  pub const GRAND_MASTER: Self = Self(Self::NAMES.len() - 1);
+}
+
+impl fmt::Display for Grade {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", Self::NAMES[self.0])
+  }
+}
+
+impl Master {
+  /// the last level.
+  pub const MAX_LEVEL: u32 = 999;
+  /// how many levels there are in a section.
+  pub const SECTION_LEVELS: u32 = 100;
+  /// gravity, in 256ths of a cell per frame, from each level on.
+  pub const GRAVITY: [(u32, u32); 30] = [
+    (0, 4),
+    (30, 6),
+    (35, 8),
+    (40, 10),
+    (50, 12),
+    (60, 16),
+    (70, 32),
+    (80, 48),
+    (90, 64),
+    (100, 80),
+    (120, 96),
+    (140, 112),
+    (160, 128),
+    (170, 144),
+    (200, 4),
+    (220, 32),
+    (230, 64),
+    (233, 96),
+    (236, 128),
+    (239, 160),
+    (243, 192),
+    (247, 224),
+    (251, 256),
+    (300, 512),
+    (330, 768),
+    (360, 1024),
+    (400, 1280),
+    (420, 1024),
+    (450, 768),
+    (500, 5120),
+  ];
+  /// the entry, lock and line clear delays of each section, in frames.
+  pub const DELAYS: [(u32, u32, u32); 10] = [
+    (25, 30, 40),
+    (25, 30, 40),
+    (25, 30, 40),
+    (25, 30, 40),
+    (25, 30, 40),
+    (25, 30, 25),
+    (16, 27, 16),
+    (12, 24, 12),
+    (12, 20, 6),
+    (12, 17, 6),
+  ];
+  /// the time each section has to be finished in to count towards the grade.
+  pub const SECTION_TARGETS: [Duration; 10] = [
+    Duration::from_secs(60),
+    Duration::from_secs(60),
+    Duration::from_secs(60),
+    Duration::from_secs(55),
+    Duration::from_secs(55),
+    Duration::from_secs(50),
+    Duration::from_secs(50),
+    Duration::from_secs(50),
+    Duration::from_secs(45),
+    Duration::from_secs(45),
+  ];
+  /// the points it takes to reach each grade, from "8" to "S9", as in TGM.
+  pub const GRADE_POINTS: [u32; 17] = [
+    400, 800, 1400, 2000, 3500, 5500, 8000, 12000, 16000, 22000, 30000, 40000, 52000, 66000, 82000,
+    100_000, 120_000,
+  ];
+  /// the points it takes to earn the grand master grade.
+  pub const GRAND_MASTER_POINTS: u32 = 126_000;
+  /// the longest a game may take and still earn the grand master grade.
+  pub const GRAND_MASTER_TIME: Duration = Duration::from_secs(13 * 60 + 30);
+
+  /// returns how long `frames` frames last.
+  fn frames(frames: u32) -> Duration {
+    Duration::from_micros(u64::from(frames) * 1_000_000 / Level::FRAMES_PER_SECOND as u64)
+  }
+
+  /// returns the section the current level is in.
+  #[must_use]
+  pub fn section(&self) -> usize {
+    (self.level / Self::SECTION_LEVELS) as usize
+  }
+
+  /// returns how long each finished section took.
+  pub fn section_times(&self) -> impl Iterator<Item = Duration> + '_ {
+    let starts = std::iter::once(Duration::ZERO).chain(self.sections.iter().copied());
+    self.sections.iter().zip(starts).map(|(end, start)| *end - start)
+  }
+
+  /// returns how many finished sections beat their target time.
+  #[must_use]
+  pub fn sections_on_time(&self) -> usize {
+    self
+      .section_times()
+      .zip(Self::SECTION_TARGETS)
+      .filter(|(time, target)| time <= target)
+      .count()
+  }
+
+  /// returns the grade the player has earned for the points scored. the
+  /// grand master grade also takes reaching the last level with every
+  /// section on time, within [`Self::GRAND_MASTER_TIME`].
+  #[must_use]
+  pub fn grade(&self, stats: &Stats) -> Grade {
+    if self.level >= Self::MAX_LEVEL
+      && self.points >= Self::GRAND_MASTER_POINTS
+      && self.sections_on_time() == Self::SECTION_TARGETS.len()
+      && stats.time <= Self::GRAND_MASTER_TIME
+    {
+      return Grade::GRAND_MASTER;
+    }
+    let grade = Self::GRADE_POINTS
+      .iter()
+      .filter(|points| self.points >= **points)
+      .count();
+    Grade(grade)
+  }
+
+  /// scores a clear of `lines` lines as TGM does, from the level before it:
+  /// a quarter of the level and lines, rounded up, times the lines, the
+  /// combo, and 4 for a perfect clear. a lock without a clear ends the combo.
+  fn score(&mut self, lines: u32, perfect_clear: bool) {
+    if lines == 0 {
+      self.combo = 0;
+      return;
+    }
+    self.combo += 2 * lines - 2;
+    let bravo = if perfect_clear { 4 } else { 1 };
+    self.points += (self.level + lines).div_ceil(4) * lines * (self.combo + 1) * bravo;
+  }
+}
+
+impl Mode for Master {
+  fn outcome(&self, _score: &Score, _stats: &Stats) -> Option<Outcome> {
+    (self.level >= Self::MAX_LEVEL).then_some(Outcome::Completed)
+  }
+
+  /// the mode keeps its own level, which lines alone don't raise.
+  fn level_goal(&self) -> Option<u32> {
+    None
+  }
+
+  fn timing(&self, _score: &Score, _settings: &Settings) -> Timing {
+    let gravity = Self::GRAVITY
+      .iter()
+      .rev()
+      .find(|(level, _)| self.level >= *level)
+      .map_or(Self::GRAVITY[0].1, |(_, gravity)| *gravity);
+    let (are, lock_delay, line_clear_delay) = Self::DELAYS[self.section().min(Self::DELAYS.len() - 1)];
+    Timing {
+      gravity: gravity as f32 / 256.0,
+      are: Self::frames(are),
+      lock_delay: Self::frames(lock_delay),
+      line_clear_delay: Self::frames(line_clear_delay),
+    }
+  }
+
+  fn on_spawn(&mut self, _stats: &Stats) {
+    // only a line clear may finish a section.
+    if self.level % Self::SECTION_LEVELS != Self::SECTION_LEVELS - 1 && self.level < Self::MAX_LEVEL - 1 {
+      self.level += 1;
+    }
+  }
+
+  fn on_lock(&mut self, cleared: usize, perfect_clear: bool, stats: &Stats) {
+    self.score(cleared as u32, perfect_clear);
+    self.level = (self.level + cleared as u32).min(Self::MAX_LEVEL);
+    let finished = if self.level >= Self::MAX_LEVEL {
+      Self::SECTION_TARGETS.len()
+    } else {
+      self.section()
+    };
+    while self.sections.len() < finished {
+      self.sections.push(stats.time);
+    }
+  }
+
+  fn progress(&self) -> Option<String> {
+    let stop = ((self.section() as u32 + 1) * Self::SECTION_LEVELS).min(Self::MAX_LEVEL);
+    Some(format!("Level: {}/{}\n", self.level, stop))
+  }
+
+  fn summary(&self, _score: &Score, stats: &Stats) -> Vec<String> {
+    vec![
+      format!("Level: {}", self.level),
+      format!("Points: {}", self.points),
+      format!("Grade: {}", self.grade(stats)),
+      format!(
+        "Sections on Time: {}/{}",
+        self.sections_on_time(),
+        self.sections.len()
+      ),
+    ]
+  }
+}
+
+/// score as many points as possible before the time runs out.
+#[derive(Debug, Clone, Copy)]
+pub struct Ultra {
+  /// how long the player has.
+  pub time: Duration,
+}
+
+impl Mode for Ultra {
+  fn outcome(&self, _score: &Score, stats: &Stats) -> Option<Outcome> {
+    (stats.time >= self.time).then_some(Outcome::TimeUp)
+  }
+
+  fn time_left(&self, stats: &Stats) -> Option<Duration> {
+    Some(self.time.saturating_sub(stats.time))
+  }
+}
+
+/// how a game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+  /// the player met the mode's goal.
+  Completed,
+  /// the mode's time limit ran out.
+  TimeUp,
+  /// the player stacked too high.
+  ToppedOut(TopOut),
+}
+
+impl fmt::Display for Outcome {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Completed => write!(f, "Finished!"),
+      Self::TimeUp => write!(f, "Time's Up!"),
+      Self::ToppedOut(top_out) => write!(f, "Game Over ({top_out})"),
+    }
+  }
+}
+
+impl Outcome {
+  /// returns true if the game ended without the player topping out.
+  #[must_use]
+  pub fn is_win(&self) -> bool {
+    !matches!(self, Self::ToppedOut(_))
+  }
+}
+
+/// the game modes a game may be played in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum GameMode {
+  /// play until topping out.
+  #[default]
+  Endless,
+  /// clear a number of lines as fast as possible.
+  Sprint {
+    /// how many lines finish the race.
+    lines: u32,
+  },
+  /// level up every [`Score::LINES_PER_LEVEL`] lines, until a line goal (if any).
+  Marathon {
+    /// how many lines finish the marathon, or [`None`] to play until topping out.
+    lines: Option<u32>,
+  },
+  /// dig through a number of garbage rows as fast as possible.
+  Dig {
+    /// how many garbage rows finish the race.
+    lines: u32,
+    /// how often another garbage row rises, if they rise at all.
+    rise: Option<Duration>,
+  },
+  /// practice without pressure, undoing placements and turning off gravity.
+  Zen,
+  /// climb to level 999 under 20G, racing each section against the clock.
+  Master,
+  /// score as many points as possible before the time runs out.
+  Ultra {
+    /// how long the player has.
+    time: Duration,
+  },
+}
+
+impl fmt::Display for GameMode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Endless => write!(f, "Endless"),
+      Self::Sprint { lines } => write!(f, "Sprint {lines}L"),
+      Self::Marathon { lines: Some(lines) } => write!(f, "Marathon {lines}L"),
+      Self::Marathon { lines: None } => write!(f, "Marathon Endless"),
+      Self::Dig { lines, rise: None } => write!(f, "Dig {lines}L"),
+      Self::Dig { lines, rise: Some(rise) } => write!(f, "Dig {lines}L +1/{}s", rise.as_secs()),
+      Self::Zen => write!(f, "Zen"),
+      Self::Master => write!(f, "Master"),
+      Self::Ultra { time } => write!(f, "Ultra {}:{:02}", time.as_secs() / 60, time.as_secs() % 60),
+    }
+  }
+}
+
+impl Cycle for GameMode {
+  fn options() -> &'static [Self] {
+    &Self::ALL
+  }
+}
+
+impl GameMode {
+  /// every mode, in the order they are cycled through in the menu.
+  pub const ALL: [Self; 16] = [
+    Self::Endless,
+    Self::Sprint { lines: 20 },
+    Self::Sprint { lines: 40 },
+    Self::Sprint { lines: 100 },
+    Self::Marathon { lines: Some(150) },
+    Self::Marathon { lines: Some(200) },
+    Self::Marathon { lines: None },
+    Self::Dig { lines: 10, rise: None },
+    Self::Dig { lines: 18, rise: None },
+    Self::Dig { lines: 100, rise: None },
+    Self::Dig { lines: 18, rise: Some(Duration::from_secs(5)) },
+    Self::Zen,
+    Self::Master,
+    Self::Ultra { time: Duration::from_secs(120) },
+    Self::Ultra { time: Duration::from_secs(180) },
+    Self::Ultra { time: Duration::from_secs(300) },
+  ];
+
+  /// builds the rules for this mode.
+  #[must_use]
+  pub fn build(self) -> Box<dyn Mode> {
+    match self {
+      Self::Endless => Box::new(Endless),
+      Self::Sprint { lines } => Box::new(Sprint { lines }),
+      Self::Marathon { lines } => Box::new(Marathon { lines }),
+      Self::Dig { lines, rise } => Box::new(Dig { lines, rise }),
+      Self::Zen => Box::new(Zen),
+      Self::Master => Box::new(Master::default()),
+      Self::Ultra { time } => Box::new(Ultra { time }),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn every_mode_but_sprint_and_master_levels_up_by_lines() {
+    for mode in GameMode::ALL {
+      let levels = !matches!(mode, GameMode::Sprint { .. } | GameMode::Master);
+      let goal = levels.then_some(Score::LINES_PER_LEVEL);
+      assert_eq!(mode.build().level_goal(), goal, "{mode}");
+    }
+  }
+
+  /// game stats at `secs` seconds in.
+  fn at(secs: u64) -> Stats {
+    Stats {
+      time: Duration::from_secs(secs),
+      ..default()
+    }
+  }
+
+  #[test]
+  fn master_level_stops_at_the_end_of_each_section_until_a_clear() {
+    let mut master = Master {
+      level: 98,
+      ..default()
+    };
+    master.on_spawn(&at(0));
+    master.on_spawn(&at(0));
+    assert_eq!(master.level, 99);
+    master.on_lock(1, false, &at(0));
+    assert_eq!(master.level, 100);
+
+    master.level = 997;
+    master.on_spawn(&at(0));
+    master.on_spawn(&at(0));
+    assert_eq!(master.level, 998);
+  }
+
+  #[test]
+  fn master_records_when_each_section_is_finished() {
+    let mut master = Master {
+      level: 98,
+      ..default()
+    };
+    master.on_lock(1, false, &at(40));
+    assert!(master.sections.is_empty());
+    master.on_lock(2, false, &at(50));
+    assert_eq!(master.sections, [Duration::from_secs(50)]);
+
+    // reaching 999 finishes every section left at once.
+    master.level = 998;
+    master.on_lock(4, false, &at(700));
+    assert_eq!(master.level, Master::MAX_LEVEL);
+    assert_eq!(master.sections.len(), Master::SECTION_TARGETS.len());
+    assert_eq!(master.sections[1..], [Duration::from_secs(700); 9]);
+  }
+
+  #[test]
+  fn master_times_each_section_against_its_target() {
+    let master = Master {
+      sections: [50, 110, 200].map(Duration::from_secs).to_vec(),
+      ..default()
+    };
+    let times: Vec<Duration> = master.section_times().collect();
+    assert_eq!(times, [50, 60, 90].map(Duration::from_secs));
+    // the first two make their 60 second targets.
+    assert_eq!(master.sections_on_time(), 2);
+  }
+
+  #[test]
+  fn master_reaches_20g_at_level_500() {
+    let timing = |level| {
+      let master = Master {
+        level,
+        ..default()
+      };
+      master.timing(&Score::default(), &Settings::default())
+    };
+    assert_eq!(timing(0).gravity, 4.0 / 256.0);
+    assert!(timing(499).gravity < Level::MAX_GRAVITY);
+    assert_eq!(timing(500).gravity, 5120.0 / 256.0);
+    assert!(timing(500).is_instant());
+    assert_eq!(timing(999).gravity, Level::MAX_GRAVITY);
+  }
+
+  #[test]
+  fn master_scores_clears_as_tgm_does() {
+    let mut master = Master {
+      level: 10,
+      ..default()
+    };
+    // ceil((10 + 4) / 4) * 4 lines * combo 7.
+    master.on_lock(4, false, &at(0));
+    assert_eq!(master.points, 112);
+    // ceil((14 + 1) / 4) * 1 line * combo 7, still.
+    master.on_lock(1, false, &at(0));
+    assert_eq!(master.points, 112 + 28);
+    master.on_lock(0, false, &at(0));
+    // ceil((15 + 2) / 4) * 2 lines * combo 3 * 4 for the perfect clear.
+    master.on_lock(2, true, &at(0));
+    assert_eq!(master.points, 140 + 120);
+  }
+
+  #[test]
+  fn master_grades_by_points_and_saves_grand_master_for_a_perfect_run() {
+    let targets = Master::SECTION_TARGETS;
+    let on_time: Vec<Duration> = targets
+      .iter()
+      .scan(Duration::ZERO, |time, target| {
+        *time += *target;
+        Some(*time)
+      })
+      .collect();
+    let run = Master {
+      level: Master::MAX_LEVEL,
+      sections: on_time.clone(),
+      points: Master::GRAND_MASTER_POINTS,
+      combo: 0,
+    };
+    let time = at(on_time[9].as_secs());
+    assert!(time.time <= Master::GRAND_MASTER_TIME);
+    assert_eq!(run.grade(&time), Grade::GRAND_MASTER);
+    // one slow section costs the grand master grade, leaving S9.
+    let mut slow = run.clone();
+    slow.sections[0] += Duration::from_secs(1);
+    assert_eq!(slow.grade(&time).to_string(), "S9");
+    // as does taking too long overall.
+    assert_eq!(run.grade(&at(14 * 60)).to_string(), "S9");
+
+    let failing = Master {
+      level: 350,
+      sections: on_time[..3].to_vec(),
+      points: 12_000,
+      combo: 0,
+    };
+    assert_eq!(failing.grade(&at(300)).to_string(), "1");
+    assert_eq!(Master::default().grade(&at(0)).to_string(), "9");
+  }
+}
@@ -3,22 +3,24 @@
 pub(crate) use {
   crate::{
     assets::GameAssets,
-    board::{GameBoard, GameBoardData},
+    board::{BoardConfig, GameBoard, GameBoardData, GameEngine, Geometry},
     dev_tools::DevTools,
+    engine::{Actions, Engine, EngineEvent, Inputs},
     keymap::GameEvent,
     palette,
-    score::{Score, Scoreboard},
-    state::{AppState, GameState},
-    tetromino::{Movable, Queue, Shape, Tetromino, Tile, NextTetromino},
+    rotation::{Rotation, RotationSystem},
+    score::{Level, LevelMeter, Score, Scoreboard},
+    settings::{Cycle, Settings},
+    state::{AppState, GameState, PlayPhase},
+    tetromino::{GhostTetromino, HeldTetromino, NextTetromino, Queue, Shape, Tetromino, Tile},
     timers::GameTimers,
   },
   bevy::{app::AppExit, prelude::*},
   bevy_inspector_egui::bevy_egui::{self, EguiContexts},
   rand::Rng,
   std::{
-    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    collections::VecDeque,
     fmt,
-    ops::Add,
     time::Duration,
   },
 };
@@ -33,4 +35,13 @@ pub(crate) fn despawn_screen<T: Component>(
   }
 }
 
+/// helper function to hide all top-level entities with a given component.
+pub(crate) fn hide_screen<T: Component>(
+  mut to_hide: Query<&mut Visibility, (With<T>, Without<Parent>)>,
+) {
+  for mut visibility in &mut to_hide {
+    *visibility = Visibility::Hidden;
+  }
+}
+
 build_info::build_info!(pub fn get_build_info);
@@ -0,0 +1,234 @@
+//! seeded piece randomizers, which decide the order pieces are dealt in.
+use crate::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+/// something which deals an endless sequence of [`Shape`]s.
+pub trait Randomizer: fmt::Debug + Send + Sync {
+  /// returns the next shape in the sequence.
+  fn next(&mut self) -> Shape;
+
+  /// returns a copy which deals the same sequence from here on.
+  fn clone_box(&self) -> Box<dyn Randomizer>;
+}
+
+impl Clone for Box<dyn Randomizer> {
+  fn clone(&self) -> Self {
+    self.clone_box()
+  }
+}
+
+/// the randomizers a game may be played with.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum RandomizerKind {
+  /// every 7 pieces contain exactly one of each shape.
+  #[default]
+  SevenBag,
+  /// every 14 pieces contain exactly two of each shape.
+  FourteenBag,
+  /// a shape is rolled up to 4 times (so re-rolled up to 3) while it is one of
+  /// the last 4 dealt, as in TGM.
+  History,
+  /// every shape is equally likely every time, with no memory.
+  Random,
+}
+
+impl fmt::Display for RandomizerKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::SevenBag => write!(f, "7-Bag"),
+      Self::FourteenBag => write!(f, "14-Bag"),
+      Self::History => write!(f, "History"),
+      Self::Random => write!(f, "Random"),
+    }
+  }
+}
+
+impl Cycle for RandomizerKind {
+  fn options() -> &'static [Self] {
+    &Self::ALL
+  }
+}
+
+impl RandomizerKind {
+  /// every randomizer, in the order they are cycled through in the menu.
+  pub const ALL: [Self; 4] = [Self::SevenBag, Self::FourteenBag, Self::History, Self::Random];
+
+  /// creates a randomizer of this kind, which deals the same sequence for the same `seed`.
+  #[must_use]
+  pub fn build(self, seed: u64) -> Box<dyn Randomizer> {
+    match self {
+      Self::SevenBag => Box::new(Bag::new(1, seed)),
+      Self::FourteenBag => Box::new(Bag::new(2, seed)),
+      Self::History => Box::new(History::new(4, seed)),
+      Self::Random => Box::new(Uniform::new(seed)),
+    }
+  }
+}
+
+/// deals shuffled bags containing each shape a fixed number of times.
+#[derive(Debug, Clone)]
+pub struct Bag {
+  /// how many of each shape go into a bag.
+  copies: usize,
+  /// the shapes left in the current bag.
+  bag: Vec<Shape>,
+  rng: StdRng,
+}
+
+impl Bag {
+  #[must_use]
+  pub fn new(copies: usize, seed: u64) -> Self {
+    Self {
+      copies,
+      bag: Vec::with_capacity(copies * Shape::ALL.len()),
+      rng: StdRng::seed_from_u64(seed),
+    }
+  }
+}
+
+impl Randomizer for Bag {
+  fn next(&mut self) -> Shape {
+    if self.bag.is_empty() {
+      for _ in 0..self.copies {
+        self.bag.extend(Shape::ALL);
+      }
+      self.bag.shuffle(&mut self.rng);
+    }
+    self.bag.pop().expect("bag was just refilled")
+  }
+
+  fn clone_box(&self) -> Box<dyn Randomizer> {
+    Box::new(self.clone())
+  }
+}
+
+/// deals shapes which are unlikely to repeat any of the last 4 dealt, by
+/// re-rolling a limited number of times. never opens with an S, Z or O.
+#[derive(Debug, Clone)]
+pub struct History {
+  /// how many times to roll before accepting a repeated shape.
+  rolls: usize,
+  /// the last 4 shapes dealt, oldest first.
+  history: VecDeque<Shape>,
+  /// has the first shape been dealt yet?
+  started: bool,
+  rng: StdRng,
+}
+
+impl History {
+  #[must_use]
+  pub fn new(rolls: usize, seed: u64) -> Self {
+    Self {
+      rolls,
+      history: VecDeque::from([Shape::Z; 4]),
+      started: false,
+      rng: StdRng::seed_from_u64(seed),
+    }
+  }
+}
+
+impl Randomizer for History {
+  fn next(&mut self) -> Shape {
+    let shape = if self.started {
+      let mut shape = Shape::random(&mut self.rng);
+      for _ in 1..self.rolls {
+        if !self.history.contains(&shape) {
+          break;
+        }
+        shape = Shape::random(&mut self.rng);
+      }
+      shape
+    } else {
+      self.started = true;
+      *[Shape::I, Shape::J, Shape::L, Shape::T]
+        .choose(&mut self.rng)
+        .expect("not empty")
+    };
+    self.history.pop_front();
+    self.history.push_back(shape);
+    shape
+  }
+
+  fn clone_box(&self) -> Box<dyn Randomizer> {
+    Box::new(self.clone())
+  }
+}
+
+/// deals every shape with equal probability, every time.
+#[derive(Debug, Clone)]
+pub struct Uniform {
+  rng: StdRng,
+}
+
+impl Uniform {
+  #[must_use]
+  pub fn new(seed: u64) -> Self {
+    Self {
+      rng: StdRng::seed_from_u64(seed),
+    }
+  }
+}
+
+impl Randomizer for Uniform {
+  fn next(&mut self) -> Shape {
+    Shape::random(&mut self.rng)
+  }
+
+  fn clone_box(&self) -> Box<dyn Randomizer> {
+    Box::new(self.clone())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// deals the first `count` shapes from a new randomizer of the given kind.
+  fn deal(kind: RandomizerKind, seed: u64, count: usize) -> Vec<Shape> {
+    let mut randomizer = kind.build(seed);
+    (0..count).map(|_| randomizer.next()).collect()
+  }
+
+  #[test]
+  fn same_seed_deals_the_same_sequence() {
+    for kind in RandomizerKind::ALL {
+      assert_eq!(deal(kind, 42, 100), deal(kind, 42, 100), "{kind}");
+      assert_ne!(deal(kind, 42, 100), deal(kind, 43, 100), "{kind}");
+    }
+  }
+
+  #[test]
+  fn clone_deals_the_same_sequence_from_there_on() {
+    for kind in RandomizerKind::ALL {
+      let mut randomizer = kind.build(42);
+      for _ in 0..10 {
+        randomizer.next();
+      }
+      let mut copy = randomizer.clone();
+      let rest: Vec<Shape> = (0..20).map(|_| randomizer.next()).collect();
+      let copied: Vec<Shape> = (0..20).map(|_| copy.next()).collect();
+      assert_eq!(rest, copied, "{kind}");
+    }
+  }
+
+  #[test]
+  fn bags_contain_each_shape_a_fixed_number_of_times() {
+    for (kind, copies) in [(RandomizerKind::SevenBag, 1), (RandomizerKind::FourteenBag, 2)] {
+      let size = copies * Shape::ALL.len();
+      for bag in deal(kind, 7, size * 20).chunks(size) {
+        for shape in Shape::ALL {
+          let count = bag.iter().filter(|dealt| **dealt == shape).count();
+          assert_eq!(count, copies, "{kind}: {bag:?}");
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn history_never_opens_with_s_z_or_o() {
+    for seed in 0..100 {
+      let first = deal(RandomizerKind::History, seed, 1)[0];
+      assert!(![Shape::S, Shape::Z, Shape::O].contains(&first), "{first}");
+    }
+  }
+}
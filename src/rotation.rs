@@ -0,0 +1,575 @@
+//! rotation states, and the rotation systems which decide how pieces face and turn.
+use crate::{engine::Board, prelude::*};
+
+/// which way a piece is facing, relative to how it spawned.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum Rotation {
+  /// "0": the orientation the piece spawns in.
+  #[default]
+  Zero,
+  /// "R": one clockwise turn from spawn.
+  Right,
+  /// "2": two turns from spawn.
+  Two,
+  /// "L": one counter-clockwise turn from spawn.
+  Left,
+}
+
+impl Rotation {
+  /// every rotation state, in clockwise order.
+  pub const ALL: [Self; 4] = [Self::Zero, Self::Right, Self::Two, Self::Left];
+
+  /// how many clockwise quarter turns away from spawn is this?
+  #[must_use]
+  pub fn quarter_turns(self) -> usize {
+    match self {
+      Self::Zero => 0,
+      Self::Right => 1,
+      Self::Two => 2,
+      Self::Left => 3,
+    }
+  }
+
+  /// returns the state after turning `quarter_turns` times clockwise.
+  #[must_use]
+  pub fn turned(self, quarter_turns: usize) -> Self {
+    Self::ALL[(self.quarter_turns() + quarter_turns) % Self::ALL.len()]
+  }
+
+  /// returns the state after a clockwise turn.
+  #[must_use]
+  pub fn cw(self) -> Self {
+    self.turned(1)
+  }
+
+  /// returns the state after a counter-clockwise turn.
+  #[must_use]
+  pub fn ccw(self) -> Self {
+    self.turned(3)
+  }
+
+  /// returns the state after a half turn.
+  #[must_use]
+  pub fn flip(self) -> Self {
+    self.turned(2)
+  }
+}
+
+/// a set of rules for how pieces spawn, which way they face, and how they turn.
+pub trait RotationSystem: fmt::Debug + Send + Sync {
+  /// returns the tiles of `shape` facing `rotation`, within its bounding box.
+  fn cells(&self, shape: Shape, rotation: Rotation) -> [Tile; 4];
+
+  /// returns the offsets to try, in order, when turning `from` into `to`
+  /// (which is `from` turned in place, and may not fit on the `board`).
+  fn kicks(&self, board: &Board, from: &Tetromino, to: &Tetromino) -> &'static [(i32, i32)];
+
+  /// returns the color which `shape` is drawn in.
+  fn color(&self, shape: Shape) -> Color {
+    shape.color()
+  }
+
+  /// returns true if a T piece turned `quarter_turns` times clockwise into
+  /// place, with the kick at index `kick` of its [`Self::kicks`], makes a full
+  /// T-spin, even where the 3-corner rule alone would only call it a mini.
+  fn is_full_t_spin_kick(&self, _quarter_turns: usize, _kick: usize) -> bool {
+    false
+  }
+}
+
+/// the rotation systems a game may be played with.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum RotationSystemKind {
+  /// the Super Rotation System, as in modern guideline games.
+  #[default]
+  Srs,
+  /// the Arika Rotation System, as in the classic arcade games.
+  Ars,
+  /// the NES rotation system, which never kicks.
+  Nes,
+}
+
+impl fmt::Display for RotationSystemKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Srs => write!(f, "SRS"),
+      Self::Ars => write!(f, "ARS"),
+      Self::Nes => write!(f, "NES"),
+    }
+  }
+}
+
+impl Cycle for RotationSystemKind {
+  fn options() -> &'static [Self] {
+    &Self::ALL
+  }
+}
+
+impl RotationSystemKind {
+  /// every rotation system, in the order they are cycled through in the menu.
+  pub const ALL: [Self; 3] = [Self::Srs, Self::Ars, Self::Nes];
+
+  /// returns the rules of this rotation system.
+  #[must_use]
+  pub fn system(self) -> &'static dyn RotationSystem {
+    match self {
+      Self::Srs => &Srs,
+      Self::Ars => &Ars,
+      Self::Nes => &Nes,
+    }
+  }
+}
+
+/// the Super Rotation System, used by modern guideline games.
+///
+/// each turn tries the rotated piece at a list of offsets ("kicks") in order,
+/// and the first one which fits wins. offsets are `(x, y)` with `y` pointing up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Srs;
+
+impl Srs {
+  /// kicks for the J, L, S, T and Z pieces, in the order of [`Self::QUARTER_TURNS`].
+  pub const JLSTZ_KICKS: [[(i32, i32); 5]; 8] = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+  ];
+
+  /// kicks for the I piece, in the order of [`Self::QUARTER_TURNS`].
+  pub const I_KICKS: [[(i32, i32); 5]; 8] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+  ];
+
+  /// kicks for half turns of every piece except O, indexed by the starting state.
+  ///
+  /// SRS itself has no half turns; these are the kicks most modern games use.
+  pub const HALF_TURN_KICKS: [[(i32, i32); 6]; 4] = [
+    [(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)],
+    [(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)],
+    [(0, 0), (0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)],
+    [(0, 0), (-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)],
+  ];
+
+  /// the quarter turns which [`Self::JLSTZ_KICKS`] and [`Self::I_KICKS`] are listed for.
+  pub const QUARTER_TURNS: [(Rotation, Rotation); 8] = [
+    (Rotation::Zero, Rotation::Right),
+    (Rotation::Right, Rotation::Zero),
+    (Rotation::Right, Rotation::Two),
+    (Rotation::Two, Rotation::Right),
+    (Rotation::Two, Rotation::Left),
+    (Rotation::Left, Rotation::Two),
+    (Rotation::Left, Rotation::Zero),
+    (Rotation::Zero, Rotation::Left),
+  ];
+
+  /// returns the offsets to try, in order, when turning `shape` from `from` to `to`.
+  #[must_use]
+  pub fn kick_table(shape: Shape, from: Rotation, to: Rotation) -> &'static [(i32, i32)] {
+    if shape == Shape::O || from == to {
+      return &[(0, 0)];
+    }
+    if to == from.flip() {
+      return &Self::HALF_TURN_KICKS[from.quarter_turns()];
+    }
+    let i = Self::QUARTER_TURNS
+      .iter()
+      .position(|turn| *turn == (from, to))
+      .expect("every quarter turn has kicks");
+    match shape {
+      Shape::I => &Self::I_KICKS[i],
+      _ => &Self::JLSTZ_KICKS[i],
+    }
+  }
+}
+
+impl RotationSystem for Srs {
+  fn cells(&self, shape: Shape, rotation: Rotation) -> [Tile; 4] {
+    shape.cells(rotation)
+  }
+
+  fn kicks(&self, _board: &Board, from: &Tetromino, to: &Tetromino) -> &'static [(i32, i32)] {
+    Self::kick_table(from.shape, from.rotation, to.rotation)
+  }
+
+  /// the last (fifth) kick of a quarter turn, as in a T-spin triple, is
+  /// always full. no half turn kick is.
+  fn is_full_t_spin_kick(&self, quarter_turns: usize, kick: usize) -> bool {
+    quarter_turns != 2 && kick == Self::JLSTZ_KICKS[0].len() - 1
+  }
+}
+
+/// the Arika Rotation System, used by the classic arcade games.
+///
+/// pieces spawn flat side up and rest on the bottom of their bounding box.
+/// a turn which does not fit is tried one tile to the right, then one to the
+/// left, except for the I piece (which never kicks) and for the J, L and T
+/// pieces when the first tile in their way sits in the centre column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ars;
+
+impl Ars {
+  const I: [[[i32; 2]; 4]; 4] = [
+    [[0, 2], [1, 2], [2, 2], [3, 2]],
+    [[2, 3], [2, 2], [2, 1], [2, 0]],
+    [[0, 2], [1, 2], [2, 2], [3, 2]],
+    [[2, 3], [2, 2], [2, 1], [2, 0]],
+  ];
+  const J: [[[i32; 2]; 4]; 4] = [
+    [[0, 1], [1, 1], [2, 1], [2, 0]],
+    [[1, 2], [1, 1], [0, 0], [1, 0]],
+    [[0, 1], [0, 0], [1, 0], [2, 0]],
+    [[1, 2], [2, 2], [1, 1], [1, 0]],
+  ];
+  const L: [[[i32; 2]; 4]; 4] = [
+    [[0, 1], [1, 1], [2, 1], [0, 0]],
+    [[0, 2], [1, 2], [1, 1], [1, 0]],
+    [[2, 1], [0, 0], [1, 0], [2, 0]],
+    [[1, 2], [1, 1], [1, 0], [2, 0]],
+  ];
+  const S: [[[i32; 2]; 4]; 4] = [
+    [[1, 1], [2, 1], [0, 0], [1, 0]],
+    [[0, 2], [0, 1], [1, 1], [1, 0]],
+    [[1, 1], [2, 1], [0, 0], [1, 0]],
+    [[0, 2], [0, 1], [1, 1], [1, 0]],
+  ];
+  const T: [[[i32; 2]; 4]; 4] = [
+    [[0, 1], [1, 1], [2, 1], [1, 0]],
+    [[1, 2], [0, 1], [1, 1], [1, 0]],
+    [[1, 1], [0, 0], [1, 0], [2, 0]],
+    [[1, 2], [1, 1], [2, 1], [1, 0]],
+  ];
+  const Z: [[[i32; 2]; 4]; 4] = [
+    [[0, 1], [1, 1], [1, 0], [2, 0]],
+    [[2, 2], [1, 1], [2, 1], [1, 0]],
+    [[0, 1], [1, 1], [1, 0], [2, 0]],
+    [[2, 2], [1, 1], [2, 1], [1, 0]],
+  ];
+
+  /// returns true if the first tile blocking `to`, reading its bounding box
+  /// from the top left, is in the centre column.
+  fn blocked_in_centre_column(board: &Board, to: &Tetromino) -> bool {
+    let mut blocks = to.blocks;
+    blocks.sort_by_key(|block| (-block.1, block.0));
+    blocks
+      .iter()
+      .find(|block| !board.is_free(block.0, block.1))
+      .is_some_and(|block| block.0 - to.origin.0 == 1)
+  }
+}
+
+impl RotationSystem for Ars {
+  fn cells(&self, shape: Shape, rotation: Rotation) -> [Tile; 4] {
+    let table = match shape {
+      Shape::I => Self::I,
+      Shape::J => Self::J,
+      Shape::L => Self::L,
+      Shape::O => return shape.shape(),
+      Shape::S => Self::S,
+      Shape::T => Self::T,
+      Shape::Z => Self::Z,
+    };
+    table[rotation.quarter_turns()].map(std::convert::Into::into)
+  }
+
+  fn kicks(&self, board: &Board, _from: &Tetromino, to: &Tetromino) -> &'static [(i32, i32)] {
+    match to.shape {
+      Shape::I | Shape::O => &[(0, 0)],
+      Shape::J | Shape::L | Shape::T if Self::blocked_in_centre_column(board, to) => &[(0, 0)],
+      _ => &[(0, 0), (1, 0), (-1, 0)],
+    }
+  }
+
+  fn color(&self, shape: Shape) -> Color {
+    use crate::palette::{BLUE, GREEN, PEACH, PINK, RED, SKY, YELLOW};
+    match shape {
+      Shape::I => RED,
+      Shape::J => BLUE,
+      Shape::L => PEACH,
+      Shape::O => YELLOW,
+      Shape::S => PINK,
+      Shape::T => SKY,
+      Shape::Z => GREEN,
+    }
+  }
+}
+
+/// the rotation system of the NES game.
+///
+/// pieces spawn flat side up and turn in place, without ever kicking. the I,
+/// S and Z pieces only have two orientations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Nes;
+
+impl Nes {
+  const I: [[[i32; 2]; 4]; 4] = [
+    [[0, 1], [1, 1], [2, 1], [3, 1]],
+    [[2, 3], [2, 2], [2, 1], [2, 0]],
+    [[0, 1], [1, 1], [2, 1], [3, 1]],
+    [[2, 3], [2, 2], [2, 1], [2, 0]],
+  ];
+  const J: [[[i32; 2]; 4]; 4] = [
+    [[0, 1], [1, 1], [2, 1], [2, 0]],
+    [[1, 2], [1, 1], [0, 0], [1, 0]],
+    [[0, 2], [0, 1], [1, 1], [2, 1]],
+    [[1, 2], [2, 2], [1, 1], [1, 0]],
+  ];
+  const L: [[[i32; 2]; 4]; 4] = [
+    [[0, 1], [1, 1], [2, 1], [0, 0]],
+    [[0, 2], [1, 2], [1, 1], [1, 0]],
+    [[2, 2], [0, 1], [1, 1], [2, 1]],
+    [[1, 2], [1, 1], [1, 0], [2, 0]],
+  ];
+  const S: [[[i32; 2]; 4]; 4] = [
+    [[1, 1], [2, 1], [0, 0], [1, 0]],
+    [[1, 2], [1, 1], [2, 1], [2, 0]],
+    [[1, 1], [2, 1], [0, 0], [1, 0]],
+    [[1, 2], [1, 1], [2, 1], [2, 0]],
+  ];
+  const T: [[[i32; 2]; 4]; 4] = [
+    [[0, 1], [1, 1], [2, 1], [1, 0]],
+    [[1, 2], [0, 1], [1, 1], [1, 0]],
+    [[1, 2], [0, 1], [1, 1], [2, 1]],
+    [[1, 2], [1, 1], [2, 1], [1, 0]],
+  ];
+  const Z: [[[i32; 2]; 4]; 4] = [
+    [[0, 1], [1, 1], [1, 0], [2, 0]],
+    [[2, 2], [1, 1], [2, 1], [1, 0]],
+    [[0, 1], [1, 1], [1, 0], [2, 0]],
+    [[2, 2], [1, 1], [2, 1], [1, 0]],
+  ];
+}
+
+impl RotationSystem for Nes {
+  fn cells(&self, shape: Shape, rotation: Rotation) -> [Tile; 4] {
+    let table = match shape {
+      Shape::I => Self::I,
+      Shape::J => Self::J,
+      Shape::L => Self::L,
+      Shape::O => return shape.shape(),
+      Shape::S => Self::S,
+      Shape::T => Self::T,
+      Shape::Z => Self::Z,
+    };
+    table[rotation.quarter_turns()].map(std::convert::Into::into)
+  }
+
+  fn kicks(&self, _board: &Board, _from: &Tetromino, _to: &Tetromino) -> &'static [(i32, i32)] {
+    &[(0, 0)]
+  }
+
+  fn color(&self, shape: Shape) -> Color {
+    use crate::palette::{BLUE, LAVENDER, SKY};
+    match shape {
+      Shape::J | Shape::S => BLUE,
+      Shape::L | Shape::Z => SKY,
+      Shape::I | Shape::O | Shape::T => LAVENDER,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// a turn of a piece from `Tile(4, 4)`, the kick it should take, the cells
+  /// filled around it, and where its origin should end up. the filled cells
+  /// block every kick before the one expected, and none of them block it.
+  type Case = (Shape, Rotation, Rotation, usize, &'static [(i32, i32)], Tile);
+
+  const CASES: [Case; 106] = [
+    // J, L, S, T and Z quarter turns.
+    (Shape::J, Rotation::Zero, Rotation::Right, 0, &[], Tile(4, 4)),
+    (Shape::L, Rotation::Zero, Rotation::Right, 1, &[(5, 6)], Tile(3, 4)),
+    (Shape::S, Rotation::Zero, Rotation::Right, 2, &[(5, 4), (6, 4)], Tile(3, 5)),
+    (Shape::Z, Rotation::Zero, Rotation::Right, 3, &[(4, 5), (5, 4)], Tile(4, 2)),
+    (Shape::Z, Rotation::Zero, Rotation::Right, 4, &[(4, 5), (5, 2), (6, 6)], Tile(3, 2)),
+    (Shape::L, Rotation::Right, Rotation::Zero, 0, &[], Tile(4, 4)),
+    (Shape::S, Rotation::Right, Rotation::Zero, 1, &[(4, 5)], Tile(5, 4)),
+    (Shape::T, Rotation::Right, Rotation::Zero, 2, &[(4, 5), (6, 6)], Tile(5, 3)),
+    (Shape::Z, Rotation::Right, Rotation::Zero, 3, &[(5, 6), (6, 4)], Tile(4, 6)),
+    (Shape::J, Rotation::Right, Rotation::Zero, 4, &[(6, 5), (4, 7), (6, 4)], Tile(5, 6)),
+    (Shape::S, Rotation::Right, Rotation::Two, 0, &[], Tile(4, 4)),
+    (Shape::T, Rotation::Right, Rotation::Two, 1, &[(4, 5)], Tile(5, 4)),
+    (Shape::Z, Rotation::Right, Rotation::Two, 2, &[(4, 5), (7, 4)], Tile(5, 3)),
+    (Shape::J, Rotation::Right, Rotation::Two, 3, &[(6, 4), (6, 5)], Tile(4, 6)),
+    (Shape::L, Rotation::Right, Rotation::Two, 4, &[(6, 5), (4, 6), (5, 3)], Tile(5, 6)),
+    (Shape::T, Rotation::Two, Rotation::Right, 0, &[], Tile(4, 4)),
+    (Shape::Z, Rotation::Two, Rotation::Right, 1, &[(6, 5)], Tile(3, 4)),
+    (Shape::J, Rotation::Two, Rotation::Right, 2, &[(5, 6)], Tile(3, 5)),
+    (Shape::L, Rotation::Two, Rotation::Right, 3, &[(4, 6), (5, 6)], Tile(4, 2)),
+    (Shape::S, Rotation::Two, Rotation::Right, 4, &[(4, 6), (5, 6), (6, 2)], Tile(3, 2)),
+    (Shape::Z, Rotation::Two, Rotation::Left, 0, &[], Tile(4, 4)),
+    (Shape::J, Rotation::Two, Rotation::Left, 1, &[(4, 4)], Tile(5, 4)),
+    (Shape::L, Rotation::Two, Rotation::Left, 2, &[(5, 6)], Tile(5, 5)),
+    (Shape::S, Rotation::Two, Rotation::Left, 3, &[(5, 6), (4, 5)], Tile(4, 2)),
+    (Shape::T, Rotation::Two, Rotation::Left, 4, &[(5, 6), (4, 3), (6, 6)], Tile(5, 2)),
+    (Shape::J, Rotation::Left, Rotation::Two, 0, &[], Tile(4, 4)),
+    (Shape::L, Rotation::Left, Rotation::Two, 1, &[(4, 4)], Tile(3, 4)),
+    (Shape::S, Rotation::Left, Rotation::Two, 2, &[(3, 4), (6, 5)], Tile(3, 3)),
+    (Shape::T, Rotation::Left, Rotation::Two, 3, &[(4, 4), (6, 5)], Tile(4, 6)),
+    (Shape::Z, Rotation::Left, Rotation::Two, 4, &[(5, 4), (3, 4), (5, 7)], Tile(3, 6)),
+    (Shape::L, Rotation::Left, Rotation::Zero, 0, &[], Tile(4, 4)),
+    (Shape::S, Rotation::Left, Rotation::Zero, 1, &[(6, 6)], Tile(3, 4)),
+    (Shape::T, Rotation::Left, Rotation::Zero, 2, &[(3, 5), (6, 5)], Tile(3, 3)),
+    (Shape::Z, Rotation::Left, Rotation::Zero, 3, &[(4, 6), (3, 5)], Tile(4, 6)),
+    (Shape::J, Rotation::Left, Rotation::Zero, 4, &[(3, 5), (4, 5), (4, 8)], Tile(3, 6)),
+    (Shape::S, Rotation::Zero, Rotation::Left, 0, &[], Tile(4, 4)),
+    (Shape::T, Rotation::Zero, Rotation::Left, 1, &[(5, 4)], Tile(5, 4)),
+    (Shape::Z, Rotation::Zero, Rotation::Left, 2, &[(4, 4), (5, 4)], Tile(5, 5)),
+    (Shape::J, Rotation::Zero, Rotation::Left, 3, &[(6, 6), (4, 4)], Tile(4, 2)),
+    (Shape::L, Rotation::Zero, Rotation::Left, 4, &[(5, 6), (4, 4), (5, 7)], Tile(5, 2)),
+    // I quarter turns.
+    (Shape::I, Rotation::Zero, Rotation::Right, 0, &[], Tile(4, 4)),
+    (Shape::I, Rotation::Zero, Rotation::Right, 1, &[(6, 4)], Tile(2, 4)),
+    (Shape::I, Rotation::Zero, Rotation::Right, 2, &[(4, 4), (6, 4)], Tile(5, 4)),
+    (Shape::I, Rotation::Zero, Rotation::Right, 3, &[(4, 7), (6, 4), (7, 4)], Tile(2, 3)),
+    (Shape::I, Rotation::Zero, Rotation::Right, 4, &[(4, 4), (6, 4), (7, 4)], Tile(5, 6)),
+    (Shape::I, Rotation::Right, Rotation::Zero, 0, &[], Tile(4, 4)),
+    (Shape::I, Rotation::Right, Rotation::Zero, 1, &[(4, 6)], Tile(6, 4)),
+    (Shape::I, Rotation::Right, Rotation::Zero, 2, &[(7, 6)], Tile(3, 4)),
+    (Shape::I, Rotation::Right, Rotation::Zero, 3, &[(4, 6), (7, 6)], Tile(6, 5)),
+    (Shape::I, Rotation::Right, Rotation::Zero, 4, &[(4, 6), (7, 6), (7, 7)], Tile(3, 2)),
+    (Shape::I, Rotation::Right, Rotation::Two, 0, &[], Tile(4, 4)),
+    (Shape::I, Rotation::Right, Rotation::Two, 1, &[(7, 5)], Tile(3, 4)),
+    (Shape::I, Rotation::Right, Rotation::Two, 2, &[(4, 5)], Tile(6, 4)),
+    (Shape::I, Rotation::Right, Rotation::Two, 3, &[(4, 5), (7, 5)], Tile(3, 6)),
+    (Shape::I, Rotation::Right, Rotation::Two, 4, &[(4, 5), (3, 7), (7, 5)], Tile(6, 3)),
+    (Shape::I, Rotation::Two, Rotation::Right, 0, &[], Tile(4, 4)),
+    (Shape::I, Rotation::Two, Rotation::Right, 1, &[(6, 4)], Tile(5, 4)),
+    (Shape::I, Rotation::Two, Rotation::Right, 2, &[(6, 4), (7, 4)], Tile(2, 4)),
+    (Shape::I, Rotation::Two, Rotation::Right, 3, &[(4, 4), (6, 4), (7, 6)], Tile(5, 2)),
+    (Shape::I, Rotation::Two, Rotation::Right, 4, &[(7, 4), (4, 4), (6, 4)], Tile(2, 5)),
+    (Shape::I, Rotation::Two, Rotation::Left, 0, &[], Tile(4, 4)),
+    (Shape::I, Rotation::Two, Rotation::Left, 1, &[(5, 4)], Tile(6, 4)),
+    (Shape::I, Rotation::Two, Rotation::Left, 2, &[(5, 4), (7, 4)], Tile(3, 4)),
+    (Shape::I, Rotation::Two, Rotation::Left, 3, &[(4, 4), (5, 4), (7, 4)], Tile(6, 5)),
+    (Shape::I, Rotation::Two, Rotation::Left, 4, &[(7, 6), (4, 6), (5, 4)], Tile(3, 2)),
+    (Shape::I, Rotation::Left, Rotation::Two, 0, &[], Tile(4, 4)),
+    (Shape::I, Rotation::Left, Rotation::Two, 1, &[(6, 5)], Tile(2, 4)),
+    (Shape::I, Rotation::Left, Rotation::Two, 2, &[(4, 5)], Tile(5, 4)),
+    (Shape::I, Rotation::Left, Rotation::Two, 3, &[(4, 5), (6, 5)], Tile(2, 3)),
+    (Shape::I, Rotation::Left, Rotation::Two, 4, &[(4, 5), (2, 4), (6, 5)], Tile(5, 6)),
+    (Shape::I, Rotation::Left, Rotation::Zero, 0, &[], Tile(4, 4)),
+    (Shape::I, Rotation::Left, Rotation::Zero, 1, &[(4, 6)], Tile(5, 4)),
+    (Shape::I, Rotation::Left, Rotation::Zero, 2, &[(6, 6)], Tile(2, 4)),
+    (Shape::I, Rotation::Left, Rotation::Zero, 3, &[(4, 6), (6, 6)], Tile(5, 2)),
+    (Shape::I, Rotation::Left, Rotation::Zero, 4, &[(4, 6), (6, 4), (6, 6)], Tile(2, 5)),
+    (Shape::I, Rotation::Zero, Rotation::Left, 0, &[], Tile(4, 4)),
+    (Shape::I, Rotation::Zero, Rotation::Left, 1, &[(5, 4)], Tile(3, 4)),
+    (Shape::I, Rotation::Zero, Rotation::Left, 2, &[(4, 4), (5, 4)], Tile(6, 4)),
+    (Shape::I, Rotation::Zero, Rotation::Left, 3, &[(4, 4), (5, 4), (7, 4)], Tile(3, 6)),
+    (Shape::I, Rotation::Zero, Rotation::Left, 4, &[(4, 7), (5, 4), (7, 7)], Tile(6, 3)),
+    // half turns.
+    (Shape::T, Rotation::Zero, Rotation::Two, 0, &[], Tile(4, 4)),
+    (Shape::J, Rotation::Zero, Rotation::Two, 1, &[(6, 4)], Tile(4, 5)),
+    (Shape::L, Rotation::Zero, Rotation::Two, 2, &[(4, 4), (4, 6)], Tile(5, 5)),
+    (Shape::T, Rotation::Zero, Rotation::Two, 3, &[(6, 6), (5, 4)], Tile(3, 5)),
+    (Shape::T, Rotation::Zero, Rotation::Two, 4, &[(4, 6), (5, 4), (6, 6)], Tile(5, 4)),
+    (Shape::T, Rotation::Zero, Rotation::Two, 5, &[(4, 6), (5, 4), (6, 4), (6, 6)], Tile(3, 4)),
+    (Shape::J, Rotation::Right, Rotation::Left, 0, &[], Tile(4, 4)),
+    (Shape::L, Rotation::Right, Rotation::Left, 1, &[(4, 6)], Tile(5, 4)),
+    (Shape::T, Rotation::Right, Rotation::Left, 2, &[(4, 5), (6, 4)], Tile(5, 6)),
+    (Shape::T, Rotation::Right, Rotation::Left, 3, &[(4, 5), (5, 7), (6, 4)], Tile(5, 5)),
+    (Shape::T, Rotation::Right, Rotation::Left, 4, &[(6, 6), (4, 5)], Tile(4, 6)),
+    (Shape::T, Rotation::Right, Rotation::Left, 5, &[(6, 6), (4, 5), (4, 7)], Tile(4, 5)),
+    (Shape::L, Rotation::Two, Rotation::Zero, 0, &[], Tile(4, 4)),
+    (Shape::S, Rotation::Two, Rotation::Zero, 1, &[(4, 5)], Tile(4, 3)),
+    (Shape::T, Rotation::Two, Rotation::Zero, 2, &[(5, 6), (6, 4)], Tile(3, 3)),
+    (Shape::T, Rotation::Two, Rotation::Zero, 3, &[(4, 4), (5, 6)], Tile(5, 3)),
+    (Shape::T, Rotation::Two, Rotation::Zero, 4, &[(4, 4), (5, 6), (6, 4)], Tile(3, 4)),
+    (Shape::J, Rotation::Two, Rotation::Zero, 5, &[(5, 4), (3, 5), (4, 6)], Tile(5, 4)),
+    (Shape::S, Rotation::Left, Rotation::Right, 0, &[], Tile(4, 4)),
+    (Shape::Z, Rotation::Left, Rotation::Right, 1, &[(5, 4)], Tile(3, 4)),
+    (Shape::T, Rotation::Left, Rotation::Right, 2, &[(4, 4), (6, 5)], Tile(3, 6)),
+    (Shape::T, Rotation::Left, Rotation::Right, 3, &[(4, 4), (4, 8), (6, 5)], Tile(3, 5)),
+    (Shape::J, Rotation::Left, Rotation::Right, 4, &[(4, 6), (6, 6)], Tile(4, 6)),
+    (Shape::L, Rotation::Left, Rotation::Right, 5, &[(4, 5), (4, 7), (5, 8), (6, 4)], Tile(4, 5)),
+
+    // the O never kicks, nor moves when turned.
+    (Shape::O, Rotation::Zero, Rotation::Right, 0, &[(3, 4), (6, 4), (4, 3), (4, 6)], Tile(4, 4)),
+    (Shape::O, Rotation::Right, Rotation::Left, 0, &[(3, 4), (6, 4), (4, 3), (4, 6)], Tile(4, 4)),
+  ];
+
+  /// starts a game and replaces its matrix with one holding only `filled`.
+  fn engine(filled: &[(i32, i32)]) -> Engine {
+    let mut engine = Engine::default();
+    // spawn a piece, and let the first row of gravity pass.
+    engine.step(Inputs::default(), Duration::ZERO);
+    engine.board = Board::new(engine.board.width(), engine.board.height());
+    let mut filler = Tetromino::new(Shape::O, Rotation::Zero, Tile(0, 0), &Srs);
+    for &(x, y) in filled {
+      filler.blocks = [Tile(x, y); 4];
+      engine.board.lock(&filler);
+    }
+    engine
+  }
+
+  #[test]
+  fn kicks_land_on_known_positions() {
+    for (shape, from, to, kick, filled, expected) in CASES {
+      let case = format!("{shape} {from:?} -> {to:?}, kick {kick}");
+      let mut engine = engine(filled);
+      let piece = Tetromino::new(shape, from, Tile(4, 4), &Srs);
+      assert!(engine.board.fits(&piece.blocks), "{case}");
+
+      let rotated = piece.rotated(to, &Srs);
+      let kicks = Srs.kicks(&engine.board, &piece, &rotated);
+      let (taken, kicked) = kicks
+        .iter()
+        .map(|kick| rotated.shifted(kick.0, kick.1))
+        .enumerate()
+        .find(|(_, kicked)| engine.board.fits(&kicked.blocks))
+        .expect("a kick fits");
+      assert_eq!((taken, kicked.origin), (kick, expected), "{case}");
+
+      engine.piece = Some(piece);
+      let quarter_turns = (to.quarter_turns() + 4 - from.quarter_turns()) % 4;
+      let inputs = Inputs {
+        rotate_cw: quarter_turns == 1,
+        rotate_180: quarter_turns == 2,
+        rotate_ccw: quarter_turns == 3,
+        ..default()
+      };
+      let events = engine.step(inputs, Duration::ZERO);
+      assert!(events.contains(&EngineEvent::Rotated), "{case}");
+      let piece = engine.piece.expect("the piece is still in play");
+      assert_eq!((piece.rotation, piece.origin), (to, expected), "{case}");
+      assert_eq!(piece.blocks, kicked.blocks, "{case}");
+    }
+  }
+
+  #[test]
+  fn every_quarter_turn_has_five_kicks_starting_in_place() {
+    for (from, to) in Srs::QUARTER_TURNS {
+      for shape in [Shape::I, Shape::J, Shape::L, Shape::S, Shape::T, Shape::Z] {
+        let kicks = Srs::kick_table(shape, from, to);
+        assert_eq!(kicks.len(), 5);
+        assert_eq!(kicks[0], (0, 0));
+      }
+      assert_eq!(Srs::kick_table(Shape::O, from, to), &[(0, 0)]);
+    }
+  }
+
+  #[test]
+  fn opposite_quarter_turns_kick_the_opposite_way() {
+    for (i, (from, to)) in Srs::QUARTER_TURNS.into_iter().enumerate() {
+      let back = Srs::QUARTER_TURNS.iter().position(|turn| *turn == (to, from)).unwrap();
+      for table in [Srs::JLSTZ_KICKS, Srs::I_KICKS] {
+        let reversed: Vec<(i32, i32)> = table[back].iter().map(|(x, y)| (-x, -y)).collect();
+        assert_eq!(table[i].to_vec(), reversed, "{from:?} -> {to:?}");
+      }
+    }
+  }
+}
@@ -1,37 +1,324 @@
 //! scoring system(s) and display.
 use crate::prelude::*;
 
+/// a level of the game, which sets how fast pieces fall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect)]
+pub struct Level(pub u32);
+
+impl Default for Level {
+  fn default() -> Self {
+    Self(1)
+  }
+}
+
+impl fmt::Display for Level {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+impl Level {
+  /// the fastest gravity, in cells per frame: pieces land instantly.
+  pub const MAX_GRAVITY: f32 = 20.0;
+  /// how many frames per second gravity is measured in.
+  pub const FRAMES_PER_SECOND: f32 = 60.0;
+
+  /// returns how fast pieces fall at this level, in cells per frame. this
+  /// follows the guideline curve, `(0.8 - (level - 1) * 0.007) ^ (level - 1)`
+  /// seconds per row, up to 20G.
+  #[must_use]
+  pub fn gravity(self) -> f32 {
+    let level = self.0.max(1) as f32 - 1.0;
+    let base = level.mul_add(-0.007, 0.8);
+    if base <= 0.0 {
+      return Self::MAX_GRAVITY;
+    }
+    let seconds_per_row = base.powf(level);
+    (1.0 / (seconds_per_row * Self::FRAMES_PER_SECOND)).min(Self::MAX_GRAVITY)
+  }
+
+  /// returns how long it takes a piece to fall one row at this level.
+  #[must_use]
+  pub fn fall_interval(self) -> Duration {
+    Duration::from_secs_f32(1.0 / (self.gravity() * Self::FRAMES_PER_SECOND))
+  }
+}
+
+/// how a T piece was spun into place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TSpin {
+  /// only one of the corners in front of the T is filled.
+  Mini,
+  /// both corners in front of the T are filled, or it was kicked far into place.
+  Full,
+}
+
+/// what kind of clear a locked piece made, by lines and T-spin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClearKind {
+  /// an ordinary clear of this many lines (which may be none).
+  Lines(usize),
+  /// a mini T-spin, clearing this many lines.
+  TSpinMini(usize),
+  /// a full T-spin, clearing this many lines.
+  TSpin(usize),
+}
+
+impl fmt::Display for ClearKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let (name, lines) = match *self {
+      Self::Lines(4) => return write!(f, "Tetris"),
+      Self::Lines(lines) => ("", lines),
+      Self::TSpinMini(lines) => ("T-Spin Mini ", lines),
+      Self::TSpin(lines) => ("T-Spin ", lines),
+    };
+    let lines = match lines {
+      0 => "",
+      1 => "Single",
+      2 => "Double",
+      3 => "Triple",
+      _ => "Clear",
+    };
+    write!(f, "{}", format!("{name}{lines}").trim_end())
+  }
+}
+
+impl ClearKind {
+  /// classifies a clear of `lines` lines, made with an optional T-spin.
+  #[must_use]
+  pub fn new(lines: usize, t_spin: Option<TSpin>) -> Self {
+    match t_spin {
+      None => Self::Lines(lines),
+      Some(TSpin::Mini) => Self::TSpinMini(lines),
+      Some(TSpin::Full) => Self::TSpin(lines),
+    }
+  }
+
+  /// how many lines were cleared?
+  #[must_use]
+  pub fn lines(self) -> usize {
+    match self {
+      Self::Lines(lines) | Self::TSpinMini(lines) | Self::TSpin(lines) => lines,
+    }
+  }
+
+  /// is this clear hard enough to continue a back-to-back chain?
+  #[must_use]
+  pub fn is_difficult(self) -> bool {
+    matches!(self, Self::Lines(4..) | Self::TSpinMini(1..) | Self::TSpin(1..))
+  }
+
+  /// points for this clear at level 1, before any bonuses.
+  #[must_use]
+  pub fn points(self) -> u32 {
+    match self {
+      Self::Lines(1) | Self::TSpinMini(0) => 100,
+      Self::TSpinMini(1) => 200,
+      Self::Lines(2) => 300,
+      Self::TSpin(0) | Self::TSpinMini(2) => 400,
+      Self::Lines(3) => 500,
+      Self::Lines(4) | Self::TSpin(1) => 800,
+      Self::TSpin(2) => 1200,
+      Self::TSpin(3) => 1600,
+      _ => 0,
+    }
+  }
+}
+
+/// everything that was scored for a single locked piece.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clear {
+  /// what kind of clear it was.
+  pub kind: ClearKind,
+  /// did it continue a back-to-back chain?
+  pub back_to_back: bool,
+  /// how many clears in a row came before it.
+  pub combo: u32,
+  /// did it leave the matrix empty?
+  pub perfect_clear: bool,
+  /// how many points it earned.
+  pub points: u32,
+}
+
+/// how the player has played so far, beyond the score.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stats {
+  /// how long the game has been played.
+  pub time: Duration,
+  /// how many pieces have locked.
+  pub pieces: u32,
+  /// how many keys have been pressed.
+  pub keys: u32,
+  /// how many of the cleared lines held garbage.
+  pub garbage_cleared: u32,
+  /// the time at which every [`Stats::SPLIT_LINES`] lines were cleared.
+  pub splits: Vec<Duration>,
+}
+
+impl Stats {
+  /// how many lines there are between splits.
+  pub const SPLIT_LINES: u32 = 10;
+
+  /// records a split for every [`Self::SPLIT_LINES`] lines reached, now that `lines` are cleared.
+  pub fn split(&mut self, lines: u32) {
+    while (self.splits.len() as u32 + 1) * Self::SPLIT_LINES <= lines {
+      self.splits.push(self.time);
+    }
+  }
+
+  /// pieces per second.
+  #[must_use]
+  pub fn pps(&self) -> f32 {
+    let seconds = self.time.as_secs_f32();
+    if seconds > 0.0 {
+      self.pieces as f32 / seconds
+    } else {
+      0.0
+    }
+  }
+
+  /// keys pressed per piece.
+  #[must_use]
+  pub fn kpp(&self) -> f32 {
+    if self.pieces > 0 {
+      self.keys as f32 / self.pieces as f32
+    } else {
+      0.0
+    }
+  }
+}
+
+/// formats `time` as a stopwatch would, like `1:23.456`.
+#[must_use]
+pub fn format_time(time: Duration) -> String {
+  let millis = time.as_millis();
+  format!(
+    "{}:{:02}.{:03}",
+    millis / 60_000,
+    millis / 1000 % 60,
+    millis % 1000
+  )
+}
+
 /// game's score data.
-#[derive(Resource, Default, Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Score {
   /// score points earned.
   pub points: u32,
   /// lines of blocks cleared.
   pub lines: u32,
+  /// the current level.
+  pub level: Level,
+  /// how many lines it takes to go up a level.
+  pub goal: u32,
+  /// how many more lines until the next level.
+  pub lines_to_next: u32,
+  /// was the last clear difficult enough to be followed back-to-back?
+  pub back_to_back: bool,
+  /// how many clears in a row, not counting the first, if the last piece cleared any lines.
+  pub combo: Option<u32>,
+  /// how many lines each cleared row counts as: two in big mode, where rows are twice as tall.
+  pub line_scale: u32,
+}
+
+impl Default for Score {
+  fn default() -> Self {
+    Self::new(Level::default(), Self::LINES_PER_LEVEL)
+  }
 }
 
 impl Score {
-  /// sets the score to zero.
-  pub fn reset(mut score: ResMut<Self>) {
-    score.lines = 0;
-    score.points = 0;
-  }
-  /// updates the score based on the number of lines cleared.
-  pub fn update(&mut self, rows_cleared: usize) {
-    let points = match rows_cleared {
-      0 => 0,
-      1 => 40,
-      2 => 100,
-      3 => 300,
-      4 => 1200,
-      _ => panic!("impossible number of rows cleared: {rows_cleared}"),
+  /// how many lines it takes to go up a level, unless a mode says otherwise.
+  pub const LINES_PER_LEVEL: u32 = 10;
+
+  /// points earned for each row a piece is soft dropped.
+  pub const SOFT_DROP_POINTS: u32 = 1;
+  /// points earned for each row a piece is hard dropped.
+  pub const HARD_DROP_POINTS: u32 = 2;
+
+  /// creates an empty score, starting at `level` and going up a level every `goal` lines.
+  #[must_use]
+  pub fn new(level: Level, goal: u32) -> Self {
+    Self {
+      points: 0,
+      lines: 0,
+      level,
+      goal,
+      lines_to_next: goal,
+      back_to_back: false,
+      combo: None,
+      line_scale: 1,
+    }
+  }
+
+  /// points for each step of a combo, at level 1.
+  pub const COMBO_POINTS: u32 = 50;
+
+  /// updates the score for a piece which locked with the given kind of
+  /// clear, following the guideline: everything is multiplied by the level,
+  /// difficult clears back-to-back earn half again, consecutive clears earn a
+  /// combo bonus, and emptying the matrix earns a perfect clear bonus.
+  pub fn update(&mut self, kind: ClearKind, perfect_clear: bool) -> Clear {
+    let rows_cleared = kind.lines();
+    let back_to_back = self.back_to_back && kind.is_difficult();
+    let mut points = kind.points();
+    if back_to_back {
+      points += points / 2;
+    }
+    let combo = if rows_cleared > 0 {
+      let combo = self.combo.map_or(0, |combo| combo + 1);
+      self.combo = Some(combo);
+      self.back_to_back = kind.is_difficult();
+      combo
+    } else {
+      self.combo = None;
+      0
     };
-    self.lines += rows_cleared as u32;
+    points += Self::COMBO_POINTS * combo;
+    if perfect_clear {
+      points += match rows_cleared {
+        1 => 800,
+        2 => 1200,
+        3 => 1800,
+        _ if back_to_back => 3200,
+        _ => 2000,
+      };
+    }
+    points *= self.level.0;
     self.points += points;
-    info!(
-      "player cleared {} line(s); earning {} points. total={} for {} lines.",
-      rows_cleared, points, self.points, self.lines
-    );
+    let rows_cleared = rows_cleared as u32 * self.line_scale;
+    self.lines += rows_cleared;
+    if points > 0 {
+      info!(
+        "player scored a {} (b2b={}, combo={}, perfect={}); earning {} points. total={} for {} lines.",
+        kind, back_to_back, combo, perfect_clear, points, self.points, self.lines
+      );
+    }
+
+    let mut rows_cleared = rows_cleared;
+    while self.goal > 0 && rows_cleared >= self.lines_to_next {
+      rows_cleared -= self.lines_to_next;
+      self.lines_to_next = self.goal;
+      self.level.0 += 1;
+      info!("player reached level {}.", self.level);
+    }
+    self.lines_to_next -= rows_cleared.min(self.lines_to_next);
+
+    Clear {
+      kind,
+      back_to_back,
+      combo,
+      perfect_clear,
+      points,
+    }
+  }
+  /// awards points for soft dropping the falling piece `rows` rows.
+  pub fn soft_drop(&mut self, rows: usize) {
+    self.points += Self::SOFT_DROP_POINTS * rows as u32;
+  }
+  /// awards points for hard dropping the falling piece `rows` rows.
+  pub fn hard_drop(&mut self, rows: usize) {
+    self.points += Self::HARD_DROP_POINTS * rows as u32;
   }
   /// outputs the current score as a string.
   pub fn score_text(&self) -> String {
@@ -41,6 +328,10 @@ impl Score {
   pub fn lines_text(&self) -> String {
     format!("Lines: {}\n", self.lines)
   }
+  /// outputs the current level as a string.
+  pub fn level_text(&self) -> String {
+    format!("Level: {}\n", self.level)
+  }
 }
 
 #[derive(Component)]
@@ -48,7 +339,6 @@ pub struct Scoreboard;
 
 impl Scoreboard {
   pub(super) fn setup(mut commands: Commands, assets: Res<AssetServer>) {
-    commands.insert_resource(Score::default());
     commands
       .spawn(NodeBundle {
         style: Style {
@@ -85,13 +375,37 @@ impl Scoreboard {
           },
         });
         sections.push(TextSection {
-          value: "Held:\n".to_string(),
+          value: "Level:\n".to_string(),
           style: TextStyle {
             font: assets.load("fonts/FiraSans-Bold.ttf"),
             font_size: 25.0,
             color: palette::SUBTEXT0,
           },
         });
+        sections.push(TextSection {
+          value: "Time:\n".to_string(),
+          style: TextStyle {
+            font: assets.load("fonts/FiraSans-Bold.ttf"),
+            font_size: 25.0,
+            color: palette::SUBTEXT0,
+          },
+        });
+        sections.push(TextSection {
+          value: String::new(),
+          style: TextStyle {
+            font: assets.load("fonts/FiraMono-Medium.ttf"),
+            font_size: 18.0,
+            color: palette::SUBTEXT0,
+          },
+        });
+        sections.push(TextSection {
+          value: "Seed:\n".to_string(),
+          style: TextStyle {
+            font: assets.load("fonts/FiraSans-Bold.ttf"),
+            font_size: 18.0,
+            color: palette::OVERLAY1,
+          },
+        });
         parent
           .spawn(TextBundle::from_sections(sections).with_style(Style {
             flex_direction: FlexDirection::Row,
@@ -102,18 +416,222 @@ impl Scoreboard {
       .insert(Name::new("Scoreboard"));
   }
 
+  pub(super) fn update(engine: Res<GameEngine>, mut query: Query<&mut Text, With<Self>>) {
+    for mut text in query.iter_mut() {
+      text.sections[0].value = engine.score.score_text();
+      text.sections[1].value = match (engine.mode().line_goal(), engine.mode().garbage_goal()) {
+        (Some(goal), _) => format!("Lines: {}/{}\n", engine.score.lines, goal),
+        (None, Some(goal)) => format!("Garbage: {}/{}\n", engine.stats.garbage_cleared, goal),
+        (None, None) => engine.score.lines_text(),
+      };
+      // modes which level up show the level on the [`LevelMeter`] instead.
+      text.sections[2].value = match (engine.mode().progress(), engine.mode().level_goal()) {
+        (Some(progress), _) => progress,
+        (None, Some(_)) => String::new(),
+        (None, None) => engine.score.level_text(),
+      };
+      text.sections[3].value = match engine.mode().time_left(&engine.stats) {
+        Some(left) => format!("Time Left: {}\n", format_time(left)),
+        None => format!("Time: {}\n", format_time(engine.stats.time)),
+      };
+      text.sections[4].value = engine
+        .stats
+        .splits
+        .iter()
+        .enumerate()
+        .map(|(i, split)| {
+          format!(
+            "{:>3}: {}\n",
+            (i as u32 + 1) * Stats::SPLIT_LINES,
+            format_time(*split)
+          )
+        })
+        .collect();
+      text.sections[5].value = format!(
+        "Seed: {} ({})\n",
+        engine.seed, engine.settings.randomizer
+      );
+    }
+  }
+}
+
+/// [component][`Component`]s of the level meter, which shows how far the
+/// player is from the next level, in modes which level up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub enum LevelMeter {
+  /// the panel holding the rest of the meter.
+  Panel,
+  /// the level, and how many lines are left until the next one.
+  Text,
+  /// the bar, filled as the player nears the next level.
+  Fill,
+}
+
+impl LevelMeter {
+  /// how wide the meter is, in pixels.
+  const WIDTH: f32 = 200.0;
+
+  pub(super) fn setup(mut commands: Commands, assets: Res<AssetServer>) {
+    let style = |font_size| TextStyle {
+      font: assets.load("fonts/FiraSans-Bold.ttf"),
+      font_size,
+      color: palette::SUBTEXT0,
+    };
+    commands
+      .spawn(NodeBundle {
+        style: Style {
+          size: Size::new(Val::Px(Self::WIDTH), Val::Auto),
+          flex_direction: FlexDirection::Column,
+          position_type: PositionType::Absolute,
+          position: UiRect {
+            left: Val::Percent(5.0),
+            bottom: Val::Percent(10.0),
+            ..default()
+          },
+          ..default()
+        },
+        visibility: Visibility::Hidden,
+        ..default()
+      })
+      .insert((Self::Panel, Name::new("Level Meter")))
+      .with_children(|parent| {
+        parent.spawn((
+          TextBundle::from_sections([
+            TextSection::new("Level:\n", style(25.0)),
+            TextSection::new(String::new(), style(18.0)),
+          ]),
+          Self::Text,
+        ));
+        parent
+          .spawn(NodeBundle {
+            style: Style {
+              size: Size::new(Val::Percent(100.0), Val::Px(8.0)),
+              margin: UiRect::top(Val::Px(4.0)),
+              ..default()
+            },
+            background_color: palette::SURFACE0.into(),
+            ..default()
+          })
+          .with_children(|parent| {
+            parent.spawn((
+              NodeBundle {
+                style: Style {
+                  size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                  ..default()
+                },
+                background_color: palette::SKY.into(),
+                ..default()
+              },
+              Self::Fill,
+            ));
+          });
+      });
+  }
+
+  /// [system][`System`] which shows the meter when the [`Engine`]'s mode
+  /// levels up, and fills it in.
   pub(super) fn update(
-    score: Res<Score>,
-    data: Res<GameBoardData>,
-    mut query: Query<&mut Text, With<Self>>,
+    engine: Res<GameEngine>,
+    mut panels: Query<(&Self, &mut Visibility)>,
+    mut texts: Query<(&Self, &mut Text)>,
+    mut fills: Query<(&Self, &mut Style)>,
   ) {
-    for mut text in query.iter_mut() {
-      text.sections[0].value = score.score_text();
-      text.sections[1].value = score.lines_text();
-      text.sections[2].value = data
-        .held_piece
-        .map(|pc| format!("Held: {:?}\n", pc))
-        .unwrap_or_default();
+    let goal = engine.mode().level_goal();
+    for (_, mut visibility) in panels.iter_mut().filter(|(meter, _)| **meter == Self::Panel) {
+      *visibility = if goal.is_some() {
+        Visibility::Inherited
+      } else {
+        Visibility::Hidden
+      };
+    }
+    let Some(goal) = goal.filter(|goal| *goal > 0) else {
+      return;
+    };
+    let score = &engine.score;
+    for (_, mut text) in texts.iter_mut().filter(|(meter, _)| **meter == Self::Text) {
+      text.sections[0].value = score.level_text();
+      text.sections[1].value = format!("{} lines to next level\n", score.lines_to_next);
+    }
+    let progress = (goal - score.lines_to_next) as f32 / goal as f32;
+    for (_, mut style) in fills.iter_mut().filter(|(meter, _)| **meter == Self::Fill) {
+      style.size.width = Val::Percent(progress * 100.0);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// scores each of `clears` in turn from `level`, never levelling up, and
+  /// returns the points each one earned.
+  fn points(level: u32, clears: &[(ClearKind, bool)]) -> Vec<u32> {
+    let mut score = Score::new(Level(level), 0);
+    clears
+      .iter()
+      .map(|&(kind, perfect_clear)| score.update(kind, perfect_clear).points)
+      .collect()
+  }
+
+  const NONE: (ClearKind, bool) = (ClearKind::Lines(0), false);
+
+  #[test]
+  fn difficult_clears_in_a_row_are_worth_half_again() {
+    let clears = [
+      (ClearKind::Lines(4), false),
+      NONE,
+      (ClearKind::Lines(4), false),
+      NONE,
+      (ClearKind::TSpin(2), false),
+      NONE,
+      // spins without lines neither earn the bonus nor break the chain.
+      (ClearKind::TSpin(0), false),
+      (ClearKind::TSpinMini(1), false),
+      NONE,
+      (ClearKind::Lines(1), false),
+      NONE,
+      (ClearKind::Lines(4), false),
+    ];
+    assert_eq!(points(1, &clears), [800, 0, 1200, 0, 1800, 0, 400, 300, 0, 100, 0, 800]);
+  }
+
+  #[test]
+  fn clears_in_a_row_earn_a_growing_combo() {
+    let clears = [
+      (ClearKind::Lines(1), false),
+      (ClearKind::Lines(2), false),
+      (ClearKind::Lines(1), false),
+      NONE,
+      (ClearKind::Lines(1), false),
+    ];
+    assert_eq!(points(1, &clears), [100, 350, 200, 0, 100]);
+    assert_eq!(points(2, &clears), [200, 700, 400, 0, 200]);
+  }
+
+  #[test]
+  fn perfect_clears_earn_a_bonus_times_the_level() {
+    let clears = [
+      (ClearKind::Lines(1), true),
+      NONE,
+      (ClearKind::Lines(2), true),
+      NONE,
+      (ClearKind::Lines(3), true),
+      NONE,
+      (ClearKind::Lines(4), true),
+      NONE,
+      (ClearKind::Lines(4), true),
+    ];
+    assert_eq!(points(3, &clears), [2700, 0, 4500, 0, 6900, 0, 8400, 0, 13200]);
+  }
+
+  #[test]
+  fn clearing_the_goal_levels_up() {
+    let mut score = Score::new(Level(1), 10);
+    for _ in 0..2 {
+      score.update(ClearKind::Lines(4), false);
     }
+    assert_eq!((score.level, score.lines), (Level(1), 8));
+    score.update(ClearKind::Lines(3), false);
+    assert_eq!((score.level, score.lines_to_next), (Level(2), 9));
   }
 }
@@ -0,0 +1,123 @@
+//! player-selectable settings, which each new game is created from.
+use crate::{
+  board::StackVisibility, mode::GameMode, prelude::*, randomizer::RandomizerKind,
+  rotation::RotationSystemKind,
+};
+
+/// resource holding the settings chosen in the menu.
+#[derive(Debug, Clone, PartialEq, Eq, Resource, Reflect)]
+pub struct Settings {
+  /// which mode is played, and so how the game ends.
+  pub mode: GameMode,
+  /// which randomizer deals the pieces.
+  pub randomizer: RandomizerKind,
+  /// seed for the randomizer. when unset, each game picks its own.
+  pub seed: Option<u64>,
+  /// how pieces spawn and turn.
+  pub rotation_system: RotationSystemKind,
+  /// how many upcoming pieces are shown, between 1 and [`Settings::MAX_PREVIEW`].
+  pub preview_count: usize,
+  /// is the falling piece's landing spot shown?
+  pub ghost: bool,
+  /// how much of the settled stack is shown.
+  pub stack: StackVisibility,
+  /// big mode: is every cell two standard cells across and up, on a field half as wide and tall?
+  /// only applies to boards which [fit it][`crate::board::BoardConfig::fits_big`].
+  pub big: bool,
+  /// how long a piece may rest on the stack before it locks.
+  pub lock_delay: Duration,
+  /// delayed auto shift: how long left or right is held before it repeats.
+  pub das: Duration,
+  /// auto repeat rate: how long between repeated shifts. zero shifts instantly.
+  pub arr: Duration,
+  /// soft drop factor: how many times faster than gravity soft drop falls.
+  pub sdf: u32,
+  /// the level each game starts at, between 1 and [`Settings::MAX_START_LEVEL`].
+  pub start_level: u32,
+  /// entry delay (ARE): how long after a piece locks until the next one spawns.
+  pub are: Duration,
+  /// how long full rows stay on the board before they are removed.
+  pub line_clear_delay: Duration,
+  /// does locking a piece partly above the visible field (without clearing a line) end the game?
+  pub partial_lock_out: bool,
+}
+
+/// a setting which the menu steps through, one option per click.
+pub trait Cycle: Copy + PartialEq + 'static {
+  /// every option, in the order the menu steps through them.
+  fn options() -> &'static [Self];
+
+  /// returns the option after this one, wrapping around. a value which is
+  /// not one of the options steps to the first.
+  #[must_use]
+  fn cycle(self) -> Self {
+    let options = Self::options();
+    let i = options.iter().position(|option| *option == self);
+    options[i.map_or(0, |i| (i + 1) % options.len())]
+  }
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    Self {
+      mode: GameMode::default(),
+      randomizer: RandomizerKind::default(),
+      seed: None,
+      rotation_system: RotationSystemKind::default(),
+      preview_count: 5,
+      ghost: true,
+      stack: StackVisibility::default(),
+      big: false,
+      lock_delay: Duration::from_millis(500),
+      das: Duration::from_millis(167),
+      arr: Duration::from_millis(33),
+      sdf: 20,
+      start_level: 1,
+      are: Duration::from_millis(100),
+      line_clear_delay: Duration::from_millis(300),
+      partial_lock_out: false,
+    }
+  }
+}
+
+impl Settings {
+  /// the most upcoming pieces which may be shown.
+  pub const MAX_PREVIEW: usize = 6;
+
+  /// the highest level a game may start at.
+  pub const MAX_START_LEVEL: u32 = 20;
+
+  /// returns the preview count after this one, wrapping around.
+  #[must_use]
+  pub fn cycle_preview(&self) -> usize {
+    self.preview_count % Self::MAX_PREVIEW + 1
+  }
+
+  /// returns the starting level after this one, wrapping around.
+  #[must_use]
+  pub fn cycle_start_level(&self) -> u32 {
+    self.start_level % Self::MAX_START_LEVEL + 1
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{board::StackVisibility, randomizer::RandomizerKind};
+
+  #[test]
+  fn cycle_steps_through_every_option_and_wraps() {
+    let mut kind = RandomizerKind::ALL[0];
+    for expected in RandomizerKind::ALL.iter().cycle().skip(1).take(8) {
+      kind = kind.cycle();
+      assert_eq!(kind, *expected);
+    }
+    assert_eq!(StackVisibility::ALL.last().unwrap().cycle(), StackVisibility::ALL[0]);
+  }
+
+  #[test]
+  fn cycle_starts_over_from_a_value_which_is_not_an_option() {
+    let custom = BoardConfig { width: 7, ..default() };
+    assert_eq!(custom.cycle(), BoardConfig::ALL[0]);
+  }
+}
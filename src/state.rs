@@ -45,6 +45,18 @@ impl GameState {
   }
 }
 
+/// what the game is doing while it is [`GameState::Playing`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, States, Default, Reflect)]
+pub enum PlayPhase {
+  /// a piece is in play (or about to spawn).
+  #[default]
+  Falling,
+  /// full rows are being cleared, before the rows above them fall.
+  LineClear,
+  /// waiting for the next piece to spawn (ARE).
+  Entry,
+}
+
 /// overall application state, distinct from the state of a game.
 #[derive(Debug, Clone, Eq, PartialEq, Hash, States, Default, Reflect)]
 pub enum AppState {
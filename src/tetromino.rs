@@ -69,15 +69,20 @@ impl fmt::Display for Shape {
 
 impl Shape {
   pub const VARIANTS: u32 = 7;
-
-  const SHAPE_I: [[i32; 2]; 4] = [[3, 0], [4, 0], [5, 0], [6, 0]];
-  const SHAPE_J: [[i32; 2]; 4] = [[3, 1], [3, 0], [4, 0], [5, 0]];
-  const SHAPE_L: [[i32; 2]; 4] = [[3, 0], [4, 0], [5, 0], [5, 1]];
-  const SHAPE_O: [[i32; 2]; 4] = [[4, 1], [4, 0], [5, 1], [5, 0]];
-  const SHAPE_S: [[i32; 2]; 4] = [[3, 0], [4, 0], [4, 1], [5, 1]];
-  const SHAPE_T: [[i32; 2]; 4] = [[3, 0], [4, 1], [4, 0], [5, 0]];
-  const SHAPE_Z: [[i32; 2]; 4] = [[3, 1], [4, 1], [4, 0], [5, 0]];
-
+  /// every shape, in alphabetical order.
+  pub const ALL: [Self; Self::VARIANTS as usize] =
+    [Self::I, Self::J, Self::L, Self::O, Self::S, Self::T, Self::Z];
+
+  // tiles of each shape in its spawn orientation, within its bounding box.
+  const SHAPE_I: [[i32; 2]; 4] = [[0, 2], [1, 2], [2, 2], [3, 2]];
+  const SHAPE_J: [[i32; 2]; 4] = [[0, 2], [0, 1], [1, 1], [2, 1]];
+  const SHAPE_L: [[i32; 2]; 4] = [[0, 1], [1, 1], [2, 1], [2, 2]];
+  const SHAPE_O: [[i32; 2]; 4] = [[0, 1], [0, 0], [1, 1], [1, 0]];
+  const SHAPE_S: [[i32; 2]; 4] = [[0, 1], [1, 1], [1, 2], [2, 2]];
+  const SHAPE_T: [[i32; 2]; 4] = [[0, 1], [1, 2], [1, 1], [2, 1]];
+  const SHAPE_Z: [[i32; 2]; 4] = [[0, 2], [1, 2], [1, 1], [2, 1]];
+
+  /// returns the tiles of this shape in its spawn orientation, within its bounding box.
   #[must_use]
   pub fn shape(&self) -> [Tile; 4] {
     Self::shape_of(self)
@@ -96,6 +101,27 @@ impl Shape {
     .map(std::convert::Into::into)
   }
 
+  /// how many tiles wide (and tall) is this shape's bounding box?
+  #[must_use]
+  pub fn box_size(&self) -> i32 {
+    match self {
+      Self::I => 4,
+      Self::O => 2,
+      _ => 3,
+    }
+  }
+
+  /// returns the tiles of this shape facing the given way, within its bounding box.
+  #[must_use]
+  pub fn cells(&self, rotation: Rotation) -> [Tile; 4] {
+    let last = self.box_size() - 1;
+    let mut cells = self.shape();
+    for _ in 0..rotation.quarter_turns() {
+      cells = cells.map(|cell| Tile(cell.1, last - cell.0));
+    }
+    cells
+  }
+
   /// returns the default color which corresponds to this piece type.
   #[inline(always)]
   #[must_use]
@@ -112,29 +138,10 @@ impl Shape {
     }
   }
 
-  /// returns a random shape.
+  /// returns a random shape, drawn from `rng`.
   #[must_use]
-  pub fn random() -> Self {
-    match rand::thread_rng().gen_range(0..Self::VARIANTS) {
-      0 => Self::I,
-      1 => Self::J,
-      2 => Self::L,
-      3 => Self::O,
-      4 => Self::S,
-      5 => Self::T,
-      6 => Self::Z,
-      _ => panic!("impossible random piece type"),
-    }
-  }
-
-  /// returns a set of random shapes .
-  #[must_use]
-  pub fn random_n(n: usize) -> BTreeSet<Self> {
-    let mut s = BTreeSet::new();
-    for _ in 0..n {
-      s.insert(Self::random());
-    }
-    s
+  pub fn random(rng: &mut impl Rng) -> Self {
+    Self::ALL[rng.gen_range(0..Self::ALL.len())]
   }
 }
 
@@ -145,54 +152,85 @@ impl Shape {
 pub struct Tetromino {
   /// the shape of this piece.
   pub shape: Shape,
+  /// which way this piece is facing.
+  pub rotation: Rotation,
+  /// the bottom-left corner of this piece's bounding box.
+  pub origin: Tile,
   /// the blocks that make up this piece.
   pub blocks: [Tile; 4],
 }
 
 impl Tetromino {
+  /// creates a piece facing `rotation`, with its bounding box at `origin`.
+  #[must_use]
+  pub fn new(shape: Shape, rotation: Rotation, origin: Tile, system: &dyn RotationSystem) -> Self {
+    Self {
+      shape,
+      rotation,
+      origin,
+      blocks: Self::shift_piece(
+        system.cells(shape, rotation),
+        Some(origin.0),
+        Some(origin.1),
+      ),
+    }
+  }
+
+  /// places a new piece of the given shape at its spawn position, centered
+  /// just above the field.
+  #[must_use]
+  pub fn spawn(shape: Shape, system: &dyn RotationSystem, geometry: &Geometry) -> Self {
+    let cells = system.cells(shape, Rotation::Zero);
+    let bottom = cells.iter().map(|cell| cell.1).min().unwrap_or_default();
+    let origin = Tile(
+      (geometry.cols as i32 - shape.box_size()) / 2,
+      geometry.rows as i32 - bottom,
+    );
+    Self::new(shape, Rotation::Zero, origin, system)
+  }
+
+  /// returns this piece moved by the given number of tiles.
+  #[must_use]
+  pub fn shifted(&self, delta_x: i32, delta_y: i32) -> Self {
+    Self {
+      origin: Tile::shift(self.origin, Some(delta_x), Some(delta_y)),
+      blocks: Self::shift_piece(self.blocks, Some(delta_x), Some(delta_y)),
+      ..self.clone()
+    }
+  }
+
+  /// returns this piece turned to face `rotation`, without moving its bounding box.
+  #[must_use]
+  pub fn rotated(&self, rotation: Rotation, system: &dyn RotationSystem) -> Self {
+    Self::new(self.shape, rotation, self.origin, system)
+  }
+
   /// spawn a moveable piece.
-  pub fn spawn_movable(self, mut commands: Commands) {
+  pub fn spawn_movable(self, commands: &mut Commands, geometry: &Geometry, color: Color) {
     let uid = rand::thread_rng().gen::<u16>();
     for (i, block) in self.blocks.into_iter().enumerate() {
       let name = Name::new(format!("{}{} (#{})", self.shape.to_string(), i, uid));
       commands
         .spawn(self.shape)
-        .insert(block.new_sprite(self.shape.color(), Visibility::Hidden))
+        .insert(block.new_sprite(geometry, color, Visibility::Hidden))
         .insert(block)
-        .insert(name)
-        .insert(Movable {
-          can_down: true,
-          can_left: true,
-          can_right: true,
-        });
+        .insert(name);
     }
   }
 
   /// spawn a unmoveable piece.
-  pub fn spawn_frozen(self, mut commands: Commands) {
+  pub fn spawn_frozen(self, commands: &mut Commands, geometry: &Geometry) {
     let uid = rand::thread_rng().gen::<u16>();
     for (i, block) in self.blocks.into_iter().enumerate() {
       let name = Name::new(format!("{}{} (#{})", self.shape.to_string(), i, uid));
       commands
         .spawn(self.shape)
-        .insert(block.new_sprite(self.shape.color(), Visibility::Hidden))
+        .insert(block.new_sprite(geometry, self.shape.color(), Visibility::Hidden))
         .insert(block)
         .insert(name);
     }
   }
 
-  #[must_use]
-  pub fn random_n(n: usize) -> Vec<Self> {
-    // info!("random 7 pieces: {:?}", result);
-    Shape::random_n(n)
-      .iter()
-      .map(|p| Self {
-        shape: *p,
-        blocks: Self::shift_piece(p.shape(), None, Some(20)),
-      })
-      .collect()
-  }
-
   #[must_use]
   pub fn shift_piece(
     mut blocks: [Tile; 4],
@@ -221,64 +259,6 @@ impl Tetromino {
   }
 }
 
-/// a [`Component`] which represents the ability of an [`Entity`] to move.
-#[derive(Component, Reflect)]
-pub struct Movable {
-  /// can this movable entity move downwards?
-  pub can_down: bool,
-  /// can this movable entity move left?
-  pub can_left: bool,
-  /// can this movable entity move right?
-  pub can_right: bool,
-}
-
-impl Movable {
-  /// marks blocks of selected piece(s) which are colliding (touching) the board
-  /// or another block. This is used to determine whether the piece can be moved
-  /// or rotated.
-  pub fn update_pieces(
-    mut piece_query: Query<(&mut Tile, &mut Movable), With<Shape>>,
-    board_query: Query<&Tile, Without<Shape>>,
-  ) {
-    let mut can_down = true;
-    let mut can_left = true;
-    let mut can_right = true;
-
-    for (block, _) in &mut piece_query {
-      if block.0 == 0 {
-        can_left = false;
-      }
-      if block.0 == 9 {
-        can_right = false;
-      }
-      if block.1 == 0 {
-        can_down = false;
-      }
-    }
-
-    for (block, _) in &piece_query {
-      for board_block in &board_query {
-        if board_block.1 == block.1 && block.0 > 0 && board_block.0 == block.0 - 1 {
-          can_left = false;
-        }
-        if board_block.1 == block.1 && board_block.0 == block.0 + 1 {
-          can_right = false;
-        }
-        if board_block.0 == block.0 && block.1 > 0 && board_block.1 == block.1 - 1 {
-          can_down = false;
-        }
-      }
-    }
-
-    // update the MoveablePiece component with our new values.
-    for (_, mut movable) in &mut piece_query {
-      movable.can_left = can_left;
-      movable.can_right = can_right;
-      movable.can_down = can_down;
-    }
-  }
-}
-
 /// represents the queue of incoming game pieces.
 #[derive(Debug, Resource)]
 pub struct Queue(pub VecDeque<Tetromino>);
@@ -289,9 +269,9 @@ pub struct Queue(pub VecDeque<Tetromino>);
 pub struct Tile(pub i32, pub i32);
 
 impl Tile {
-  /// the length of a block.
+  /// the length of a block, on the standard [`Geometry`].
   pub const LENGTH: f32 = 30.0;
-  /// the length of a sticker.
+  /// the length of a sticker, on the standard [`Geometry`].
   pub const STICKER_LENGTH: f32 = 28.0;
 
   /// [system][`System`] which clears all the blocks from the board.
@@ -302,25 +282,12 @@ impl Tile {
   }
 
   #[must_use]
-  pub fn translation(&self) -> Vec3 {
-    Vec3 {
-      x: (self.0 as f32 - (f32::from(super::GameBoard::COL_COUNT) / 2.0) + 0.5) * Self::LENGTH,
-      y: (self.1 as f32 - (f32::from(super::GameBoard::ROW_COUNT) / 2.0) + 0.5) * Self::LENGTH,
-      z: 0.0,
-    }
-  }
-
-  #[must_use]
-  pub fn new_sprite(&self, color: Color, visibility: Visibility) -> SpriteBundle {
+  pub fn new_sprite(&self, geometry: &Geometry, color: Color, visibility: Visibility) -> SpriteBundle {
     SpriteBundle {
       sprite: Sprite { color, ..default() },
       transform: Transform {
-        scale: Vec3::new(
-          Self::STICKER_LENGTH,
-          Self::STICKER_LENGTH,
-          Self::STICKER_LENGTH,
-        ),
-        translation: self.translation(),
+        scale: Vec3::splat(geometry.sticker_length()),
+        translation: geometry.translation(*self),
         ..default()
       },
       visibility,
@@ -351,92 +318,181 @@ impl From<[i32; 2]> for Tile {
   }
 }
 
-#[must_use]
-/// checks whether selected piece(s) collide with either the board, or any other pieces.
-pub fn has_collision(
-  piece_query: &Query<(&mut Shape, &mut Tile, &mut Transform)>,
-  board_query: &Query<&Tile, Without<Shape>>,
-) -> bool {
-  for (_, block, _) in piece_query {
-    if block.0 < 0 {
-      return true;
+/// [component][`Component`] which marks a [`Tetromino`] as the **_next_** [`Tetromino`] to fall.
+#[derive(Debug, Component)]
+pub struct NextTetromino;
+
+impl NextTetromino {
+  /// system responsible for clearing the [`NextTetromino`] from [`Entity`](s).
+  pub fn clear(mut commands: Commands, query: Query<Entity, With<Self>>) {
+    for entity in &query {
+      commands.entity(entity).despawn();
     }
-    if block.0 > 9 {
-      return true;
+  }
+
+  /// how many block units wide is each cell of the preview?
+  const CELL_COLS: u8 = 5;
+  /// how many block units tall is each cell of the preview?
+  const CELL_ROWS: u8 = 3;
+
+  /// [system][`System`] which draws the upcoming [`Tetromino`]s in a panel
+  /// beside the board, soonest at the top, whenever the queue changes.
+  pub fn update(
+    mut commands: Commands,
+    engine: Res<GameEngine>,
+    mut shown: Local<Vec<Shape>>,
+    query: Query<Entity, With<Self>>,
+  ) {
+    let preview: Vec<Shape> = engine.preview().map(|piece| piece.shape).collect();
+    if preview == *shown && !query.is_empty() {
+      return;
     }
-    if block.1 < 0 {
-      return true;
+    for entity in &query {
+      commands.entity(entity).despawn();
+    }
+    *shown = preview;
+
+    // the panel sits one border's width to the right of the board, its top
+    // level with the board's.
+    let cell = Vec2::new(f32::from(Self::CELL_COLS), f32::from(Self::CELL_ROWS)) * Tile::LENGTH;
+    let size = Vec2::new(cell.x, cell.y * engine.settings.preview_count as f32);
+    let board = engine.geometry.size();
+    let center = Vec2::new(
+      (board.x + size.x) / 2.0 + 3.0 * GameBoard::BORDER_THICKNESS,
+      (board.y - size.y) / 2.0,
+    );
+    for wall in GameBoard::spawn_frame(&mut commands, center, size, "Next") {
+      commands.entity(wall).insert(NextTetromino);
     }
-  }
 
-  for (_, block, _) in piece_query {
-    for board_block in board_query {
-      if board_block.1 == block.1 && block.0 > 0 && board_block.0 == block.0 - 1 {
-        return true;
-      }
-      if board_block.1 == block.1 && board_block.0 == block.0 + 1 {
-        return true;
-      }
-      if board_block.0 == block.0 && block.1 > 0 && board_block.1 == block.1 - 1 {
-        return true;
+    for (i, piece) in engine.preview().enumerate() {
+      let color = engine.rotation_system().color(piece.shape);
+      let cell_center = Vec2::new(
+        center.x,
+        (size.y - cell.y).mul_add(0.5, center.y) - cell.y * i as f32,
+      );
+      // normalize the piece so that its bounding box sits in the middle of the cell.
+      let (min, max) = piece.blocks.iter().fold(
+        (IVec2::splat(i32::MAX), IVec2::splat(i32::MIN)),
+        |(min, max), Tile(x, y)| (min.min(IVec2::new(*x, *y)), max.max(IVec2::new(*x, *y))),
+      );
+      let middle = (min + max).as_vec2() / 2.0;
+      for block in piece.blocks {
+        // the preview is drawn at the standard size, even in big mode.
+        let mut sprite = block.new_sprite(&Geometry::default(), color, Visibility::Visible);
+        let offset = (Vec2::new(block.0 as f32, block.1 as f32) - middle) * Tile::LENGTH;
+        sprite.transform.translation = (cell_center + offset).extend(0.0);
+        commands
+          .spawn(sprite)
+          .insert(NextTetromino)
+          .insert(Name::new(format!("Next {} ({i})", piece.shape)));
       }
     }
   }
-  false
 }
 
-/// a [`System`] which automatically generates a new piece when the previous one is no longer movable.
-pub fn spawn_next_piece(
-  commands: Commands,
-  query: Query<&Shape>,
-  mut data: ResMut<GameBoardData>,
-  mut piece_queue: ResMut<Queue>,
-) {
-  if piece_queue.0.len() < Shape::VARIANTS as usize {
-    piece_queue
-      .0
-      .extend(Tetromino::random_n(Shape::VARIANTS as usize));
+/// [component][`Component`] which marks the tiles drawing where the falling
+/// [`Tetromino`] will land.
+#[derive(Debug, Component)]
+pub struct GhostTetromino;
+
+impl GhostTetromino {
+  /// how opaque the ghost is, compared to the piece it shadows.
+  const ALPHA: f32 = 0.3;
+
+  /// system responsible for clearing the [`GhostTetromino`] from [`Entity`](s).
+  pub fn clear(mut commands: Commands, query: Query<Entity, With<Self>>) {
+    for entity in &query {
+      commands.entity(entity).despawn();
+    }
   }
-  if query.is_empty() {
-    piece_queue.0.pop_front().unwrap().spawn_movable(commands);
-    // player may hold a piece again.
-    data.held = false;
+
+  /// [system][`System`] which draws a translucent copy of the falling
+  /// [`Tetromino`] where it would land, whenever that changes.
+  pub fn update(
+    mut commands: Commands,
+    engine: Res<GameEngine>,
+    mut shown: Local<Option<(Shape, [Tile; 4])>>,
+    query: Query<Entity, With<Self>>,
+  ) {
+    let ghost = engine
+      .ghost()
+      .filter(|_| engine.settings.ghost)
+      .map(|ghost| (ghost.shape, ghost.blocks));
+    if ghost == *shown && ghost.is_some() != query.is_empty() {
+      return;
+    }
+    *shown = ghost;
+    for entity in &query {
+      commands.entity(entity).despawn();
+    }
+    let Some((shape, blocks)) = ghost else {
+      return;
+    };
+    let color = engine.rotation_system().color(shape).with_a(Self::ALPHA);
+    for block in blocks {
+      let visibility = if engine.geometry.is_visible(block) {
+        Visibility::Visible
+      } else {
+        Visibility::Hidden
+      };
+      let mut sprite = block.new_sprite(&engine.geometry, color, visibility);
+      // keep the ghost beneath the piece when they overlap.
+      sprite.transform.translation.z = -1.0;
+      commands
+        .spawn(sprite)
+        .insert(GhostTetromino)
+        .insert(Name::new(format!("Ghost {shape}")));
+    }
   }
 }
 
-/// [component][`Component`] which marks a [`Tetromino`] as the **_next_** [`Tetromino`] to fall.
+/// [component][`Component`] which marks the tiles drawing the held [`Tetromino`].
 #[derive(Debug, Component)]
-pub struct NextTetromino;
+pub struct HeldTetromino;
 
-impl NextTetromino {
-  /// system responsible for clearing the [`NextTetromino`] from [`Entity`](s).
+impl HeldTetromino {
+  /// system responsible for clearing the [`HeldTetromino`] from [`Entity`](s).
   pub fn clear(mut commands: Commands, query: Query<Entity, With<Self>>) {
     for entity in &query {
       commands.entity(entity).despawn();
     }
   }
 
-  /// [system][`System`] which spawns the next [`Tetromino`] to fall, when necessary.
+  /// [system][`System`] which draws the held [`Tetromino`], greyed out while
+  /// it may not be swapped, whenever it changes.
   pub fn update(
     mut commands: Commands,
-    piece_queue: Res<Queue>,
-    mut board_data: ResMut<GameBoardData>,
+    engine: Res<GameEngine>,
+    mut shown: Local<Option<(Shape, bool)>>,
     query: Query<Entity, With<Self>>,
   ) {
-    if board_data.next_piece.is_none()
-      || piece_queue.0.front().unwrap().shape != board_data.next_piece.unwrap()
-    {
-      board_data.next_piece = Some(piece_queue.0.front().unwrap().shape);
-      for entity in &query {
-        commands.entity(entity).despawn();
-      }
-      let next = piece_queue.0.front().unwrap().shape;
-      let blocks = Tetromino::shift_piece(next.shape(), Some(-8), Some(12));
-      for i in 0..4 {
-        commands
-          .spawn(blocks[i].new_sprite(next.color(), Visibility::Visible))
-          .insert(NextTetromino);
-      }
+    let held = engine.data.held_piece.map(|shape| (shape, engine.data.held));
+    if held == *shown && held.is_some() != query.is_empty() {
+      return;
+    }
+    *shown = held;
+    for entity in &query {
+      commands.entity(entity).despawn();
+    }
+    let Some((shape, locked)) = held else {
+      return;
+    };
+    let color = if locked {
+      palette::OVERLAY0
+    } else {
+      engine.rotation_system().color(shape)
+    };
+    // the held piece is drawn at the standard size, even in big mode, and
+    // stays level with the top left corner of boards of other sizes.
+    let geometry = Geometry::default();
+    let (board, standard) = (engine.geometry.size(), geometry.size());
+    let offset = Vec2::new(standard.x - board.x, board.y - standard.y) / 2.0;
+    let piece = Tetromino::spawn(shape, engine.rotation_system(), &geometry);
+    for block in Tetromino::shift_piece(piece.blocks, Some(-8), Some(-14)) {
+      let mut sprite = block.new_sprite(&geometry, color, Visibility::Visible);
+      sprite.transform.translation += offset.extend(0.0);
+      commands.spawn(sprite).insert(HeldTetromino);
     }
   }
 }
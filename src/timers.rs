@@ -1,44 +1,311 @@
 //! timers and their associated logic.
 //!
-//! - manual movements. (left, right, down)
+//! - shifting. (delayed auto shift and auto repeat, for left and right)
+//! - soft dropping. (falling faster while down is held)
 //! - auto movements. (falling on interval)
-//! - rm_pieces. (removing pieces from the board after clear)
+//! - lock. (how long a piece may rest on the stack before it locks)
+//! - delay. (line clear delay and entry delay, between pieces)
+//! - garbage. (rising garbage, in modes which have it)
 use crate::prelude::*;
 
-/// resource that keeps track of all game-related timers for moving the piece
-/// down automatically as well as manually (by the player).
-#[derive(Debug, Resource, Reflect)]
+/// a timer which only moves when it is ticked by hand, so the [`Engine`] keeps
+/// time without bevy's [`Time`]. it finishes like bevy's own [`Timer`]: once,
+/// or as many times per tick as its duration fits into, when repeating.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect, FromReflect)]
+pub struct Ticker {
+  /// how long until the ticker finishes.
+  duration: Duration,
+  /// how long it has run since it was reset, or last finished if it repeats.
+  elapsed: Duration,
+  /// does it start over once it finishes?
+  repeating: bool,
+  /// has it finished? a repeating ticker only counts the last tick.
+  finished: bool,
+  /// how many times did it finish during the last tick?
+  times_finished: u32,
+}
+
+impl Ticker {
+  /// creates a ticker which finishes once, after `duration`.
+  #[must_use]
+  pub fn once(duration: Duration) -> Self {
+    Self {
+      duration,
+      ..default()
+    }
+  }
+
+  /// creates a ticker which finishes every `duration`.
+  #[must_use]
+  pub fn repeating(duration: Duration) -> Self {
+    Self {
+      duration,
+      repeating: true,
+      ..default()
+    }
+  }
+
+  /// advances the ticker by `dt`. a ticker which does not repeat stays finished.
+  pub fn tick(&mut self, dt: Duration) {
+    if !self.repeating && self.finished {
+      self.times_finished = 0;
+      return;
+    }
+    self.elapsed += dt;
+    self.finished = self.elapsed >= self.duration;
+    self.times_finished = if !self.finished {
+      0
+    } else if self.repeating {
+      // a repeating ticker without a duration finishes once per tick.
+      let times = self
+        .elapsed
+        .as_nanos()
+        .checked_div(self.duration.as_nanos())
+        .map_or(1, |times| times as u32);
+      self.elapsed -= self.duration * times;
+      times
+    } else {
+      self.elapsed = self.duration;
+      1
+    };
+  }
+
+  /// has the ticker finished (during the last tick, if it repeats)?
+  #[must_use]
+  pub fn finished(&self) -> bool {
+    self.finished
+  }
+
+  /// how many times did the ticker finish during the last tick?
+  #[must_use]
+  pub fn times_finished_this_tick(&self) -> u32 {
+    self.times_finished
+  }
+
+  /// how long does the ticker run before it finishes?
+  #[must_use]
+  pub fn duration(&self) -> Duration {
+    self.duration
+  }
+
+  /// changes how long the ticker runs, keeping the time it has already run.
+  pub fn set_duration(&mut self, duration: Duration) {
+    self.duration = duration;
+  }
+
+  /// how long has the ticker run?
+  #[must_use]
+  pub fn elapsed(&self) -> Duration {
+    self.elapsed
+  }
+
+  /// changes how long the ticker has run, without finishing it.
+  pub fn set_elapsed(&mut self, elapsed: Duration) {
+    self.elapsed = elapsed;
+  }
+
+  /// starts the ticker over.
+  pub fn reset(&mut self) {
+    self.elapsed = Duration::ZERO;
+    self.finished = false;
+    self.times_finished = 0;
+  }
+
+  /// returns how far along the ticker is, from `0.0` to `1.0`.
+  #[must_use]
+  pub fn percent(&self) -> f32 {
+    if self.duration.is_zero() {
+      return 1.0;
+    }
+    self.elapsed.as_secs_f32() / self.duration.as_secs_f32()
+  }
+}
+
+/// keeps track of all game-related timers for moving the piece down
+/// automatically as well as manually (by the player).
+#[derive(Debug, Reflect)]
 pub struct GameTimers {
-  /// timer between manually input moves by the player.
-  pub manual: Timer,
+  /// how far the player has charged left or right.
+  pub shift: AutoShift,
+  /// timer between rows soft dropped by the player.
+  pub soft_drop: Ticker,
   /// timer between automatic moves of the falling piece.
-  pub auto: Timer,
-  /// timer between removing pieces from the board.
-  pub rm_pieces: Timer,
-}
-
-impl FromWorld for GameTimers {
-  fn from_world(_world: &mut World) -> Self {
-    let mut manual = Timer::new(std::time::Duration::from_millis(100), TimerMode::Once);
-    manual.set_elapsed(std::time::Duration::from_millis(100));
-    let mut rm_pieces = Timer::new(std::time::Duration::from_millis(300), TimerMode::Once);
-    rm_pieces.set_elapsed(std::time::Duration::from_millis(300));
-    let mut auto = Timer::new(std::time::Duration::from_millis(1000), TimerMode::Repeating);
-    auto.set_elapsed(std::time::Duration::from_millis(1000));
+  pub auto: Ticker,
+  /// lock delay: how long the falling piece may rest on the stack before it locks.
+  pub lock: Ticker,
+  /// timer for the line clear or entry delay, between one piece locking and the next spawning.
+  pub delay: Ticker,
+  /// timer between garbage rows rising from the bottom, if the mode has them.
+  pub garbage: Option<Ticker>,
+}
+
+impl Default for GameTimers {
+  fn default() -> Self {
+    Self::new(&Settings::default())
+  }
+}
+
+impl GameTimers {
+  /// creates the timers for a game played with the given settings.
+  #[must_use]
+  pub fn new(settings: &Settings) -> Self {
+    let mut soft_drop = Ticker::repeating(Duration::from_millis(50));
+    soft_drop.set_elapsed(Duration::from_millis(50));
+    let lock = Ticker::once(settings.lock_delay);
+    let fall_interval = Level(settings.start_level).fall_interval();
+    let mut auto = Ticker::repeating(fall_interval);
+    auto.set_elapsed(fall_interval);
     Self {
-      manual,
-      rm_pieces,
+      shift: AutoShift::default(),
+      soft_drop,
       auto,
+      lock,
+      delay: Ticker::default(),
+      garbage: settings
+        .mode
+        .build()
+        .garbage_interval()
+        .map(Ticker::repeating),
     }
   }
 }
 
-impl GameTimers {
-  /// add a small offset to the automove timer to make the game progress faster as player progresses.
-  /// TODO: fix this scaling hack. this should scare parabolically, asymptotic to the player's score.
-  #[must_use]
-  pub fn calculate_score_speed_offset(score: Res<Score>) -> Duration {
-    let level = score.points / 100;
-    Duration::from_millis(u64::from(level))
+/// delayed auto shift: pressing left or right shifts the piece once, and
+/// holding it for the DAS delay shifts it again every ARR interval.
+///
+/// the charge belongs to the player rather than to the piece, so holding a
+/// direction while a new piece spawns keeps it charged.
+#[derive(Debug, Default, Clone, Reflect)]
+pub struct AutoShift {
+  /// the direction being shifted: `-1` for left, `1` for right and `0` for neither.
+  pub direction: i32,
+  /// how long the direction has been held.
+  pub charge: Duration,
+  /// was left held during the last tick?
+  left: bool,
+  /// was right held during the last tick?
+  right: bool,
+}
+
+impl AutoShift {
+  /// advances the charge by `dt` and returns how many cells to shift in
+  /// [`Self::direction`]. when both directions are held, the one pressed last
+  /// wins. an `arr` of zero shifts as far as possible, as [`usize::MAX`].
+  pub fn tick(&mut self, left: bool, right: bool, dt: Duration, das: Duration, arr: Duration) -> usize {
+    let pressed_left = left && !self.left;
+    let pressed_right = right && !self.right;
+    self.left = left;
+    self.right = right;
+    let held = |direction| (direction == -1 && left) || (direction == 1 && right);
+    let direction = if pressed_left != pressed_right {
+      if pressed_left {
+        -1
+      } else {
+        1
+      }
+    } else if held(self.direction) {
+      self.direction
+    } else if left != right {
+      if left {
+        -1
+      } else {
+        1
+      }
+    } else {
+      0
+    };
+
+    if direction != self.direction || pressed_left || pressed_right {
+      self.direction = direction;
+      self.charge = Duration::ZERO;
+      return usize::from(direction != 0);
+    }
+    if direction == 0 {
+      return 0;
+    }
+    let before = self.charge;
+    self.charge += dt;
+    if self.charge < das {
+      0
+    } else if arr.is_zero() {
+      usize::MAX
+    } else {
+      // count the repeats which came due during this tick.
+      let repeats = |charge: Duration| {
+        charge
+          .checked_sub(das)
+          .map_or(0, |charge| (charge.as_nanos() / arr.as_nanos()) as usize + 1)
+      };
+      repeats(self.charge) - repeats(before)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DAS: Duration = Duration::from_millis(167);
+  const ARR: Duration = Duration::from_millis(33);
+
+  /// ticks `shift` by `dt` while holding left and/or right, with the usual delays.
+  fn hold(shift: &mut AutoShift, left: bool, right: bool, dt: Duration) -> usize {
+    shift.tick(left, right, dt, DAS, ARR)
+  }
+
+  #[test]
+  fn pressing_shifts_once_and_repeats_only_after_das() {
+    let mut shift = AutoShift::default();
+    assert_eq!(hold(&mut shift, true, false, Duration::ZERO), 1);
+    assert_eq!(shift.direction, -1);
+    assert_eq!(hold(&mut shift, true, false, DAS - Duration::from_millis(1)), 0);
+    assert_eq!(hold(&mut shift, true, false, Duration::from_millis(1)), 1);
+  }
+
+  #[test]
+  fn charged_shift_repeats_once_per_arr() {
+    let mut shift = AutoShift::default();
+    hold(&mut shift, false, true, Duration::ZERO);
+    assert_eq!(hold(&mut shift, false, true, DAS), 1);
+    assert_eq!(hold(&mut shift, false, true, ARR - Duration::from_millis(1)), 0);
+    assert_eq!(hold(&mut shift, false, true, Duration::from_millis(1)), 1);
+    assert_eq!(hold(&mut shift, false, true, ARR * 3), 3);
+    assert_eq!(shift.direction, 1);
+  }
+
+  #[test]
+  fn zero_arr_shifts_all_the_way() {
+    let mut shift = AutoShift::default();
+    assert_eq!(shift.tick(true, false, Duration::ZERO, DAS, Duration::ZERO), 1);
+    assert_eq!(shift.tick(true, false, DAS, DAS, Duration::ZERO), usize::MAX);
+  }
+
+  #[test]
+  fn last_direction_pressed_wins_while_both_are_held() {
+    let mut shift = AutoShift::default();
+    hold(&mut shift, true, false, Duration::ZERO);
+    hold(&mut shift, true, false, DAS);
+    // pressing right takes over, with a fresh charge.
+    assert_eq!(hold(&mut shift, true, true, Duration::ZERO), 1);
+    assert_eq!(shift.direction, 1);
+    assert_eq!(hold(&mut shift, true, true, DAS - Duration::from_millis(1)), 0);
+    assert_eq!(hold(&mut shift, true, true, Duration::from_millis(1)), 1);
+    // releasing it goes back to left, which was still held, from scratch.
+    assert_eq!(hold(&mut shift, true, false, Duration::ZERO), 1);
+    assert_eq!(shift.direction, -1);
+    assert_eq!(shift.charge, Duration::ZERO);
+    // and pressing right again takes over again.
+    assert_eq!(hold(&mut shift, true, true, Duration::ZERO), 1);
+    assert_eq!(shift.direction, 1);
+  }
+
+  #[test]
+  fn releasing_both_stops_shifting() {
+    let mut shift = AutoShift::default();
+    hold(&mut shift, true, false, Duration::ZERO);
+    hold(&mut shift, true, false, DAS);
+    assert_eq!(hold(&mut shift, false, false, ARR), 0);
+    assert_eq!(shift.direction, 0);
+    assert_eq!(hold(&mut shift, false, false, DAS), 0);
   }
 }